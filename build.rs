@@ -1,4 +1,3 @@
-#![feature(non_ascii_idents)]
 #![allow(uncommon_codepoints)]
 use clap::IntoApp;
 use clap_generate::{self, generators};
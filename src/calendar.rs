@@ -1,25 +1,55 @@
+use std::borrow::Cow;
+use std::convert::TryFrom;
 use std::fmt::Formatter;
 use std::fmt::{Debug, Display};
 use std::str::FromStr;
 
-use chrono::NaiveDate;
-use chrono::TimeZone;
 use chrono::{
-    DateTime, Datelike, Duration, Local, Timelike, Weekday,
+    DateTime, Datelike, Duration, FixedOffset, Local, LocalResult,
+    NaiveDate, NaiveDateTime, NaiveTime, Offset, TimeZone, Timelike,
+    Utc, Weekday,
 };
+use chrono_tz::Tz;
 use lazy_static::lazy_static;
 use regex::Regex;
-use unwrap::unwrap;
+
+use super::{month, names};
+
+macro_rules! match_any {
+    ($value:expr, $first:expr) => {
+        $value.eq_ignore_ascii_case($first)
+    };
+    ($value:expr, $first:expr, $($pattern:expr), +) => {
+        $value.eq_ignore_ascii_case($first) || match_any!($value, $($pattern), +)
+    };
+}
+
+/// The pattern of a time of day like `16:30`, `16h30`, `4pm` or `noon`, to
+/// embed in other patterns with `concat!`
+macro_rules! time_pattern {
+    () => {
+        concat!(
+            r"(?:\d\d?(?:[:h]\d\d?(?::\d\d?)?|h)?(?:\s*(?i:[ap]\.?m\.?))?",
+            r"|(?i:noon|midnight))"
+        )
+    };
+}
 
 #[derive(Debug)]
 pub enum Timer {
     Timers(Vec<Timer>),
-    Calendar(Calendar),
+    /// A systemd calendar event, in the given zone or local time
+    Calendar(Calendar, Option<Tz>),
     Duration {
         kind: DurationKind,
         duration: InDuration,
     },
-    In(In),
+    /// A relative time, computed in the given zone or local time
+    In(In, Option<Tz>),
+    /// A repeating schedule, in the given zone or local time
+    Every(Every, Option<Tz>),
+    /// A fixed point in time, in the given zone or local time
+    At(Absolute, Option<Tz>),
 }
 
 pub enum In {
@@ -29,6 +59,9 @@ pub enum In {
     Day(InDay),
     Week(InWeek),
     Month(InMonth),
+    Year(InYear),
+    /// The next occurrence of a weekday, never today
+    Next(AtWeekDay),
 }
 
 impl Debug for In {
@@ -58,6 +91,11 @@ impl Debug for In {
             In::Month(InMonth(i, Some(a))) => {
                 write!(f, "in {}M at {}", i, a)
             }
+            In::Year(InYear(i, None)) => write!(f, "in {}y", i),
+            In::Year(InYear(i, Some(a))) => {
+                write!(f, "in {}y at {}", i, a)
+            }
+            In::Next(a) => write!(f, "next {}", a),
         }
     }
 }
@@ -72,14 +110,249 @@ impl TimerAble for DateTime<Local> {
     }
 }
 
+impl TimerAble for DateTime<chrono_tz::Tz> {
+    fn timer(&self) -> String {
+        format!(
+            "OnCalendar={} {}",
+            self.format("%Y-%m-%d %H:%M:%S"),
+            self.timezone().name()
+        )
+    }
+}
+
+impl TimerAble for DateTime<Utc> {
+    fn timer(&self) -> String {
+        format!("OnCalendar={} UTC", self.format("%Y-%m-%d %H:%M:%S"))
+    }
+}
+
+/// How to resolve local times that do not exist or exist twice because
+/// of a daylight saving time change
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum DstPolicy {
+    #[default]
+    Earliest,
+    Latest,
+    Error,
+}
+
+impl FromStr for DstPolicy {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "earliest" => Ok(DstPolicy::Earliest),
+            "latest" => Ok(DstPolicy::Latest),
+            "error" => Ok(DstPolicy::Error),
+            _ => Err(ParseError(format!(
+                "`{}` is not a DST policy, use earliest, latest or error",
+                s
+            ))),
+        }
+    }
+}
+
+/// Renders a local time in `tz`, resolving it with `dst` if a daylight
+/// saving time change makes it ambiguous or skips it
+///
+/// Ambiguous times are rendered in UTC, as the wall clock time alone
+/// would not tell systemd which one is meant. Skipped times are moved by
+/// the length of the gap, before it for earliest, after it for latest.
+fn resolve<Tz: TimeZone>(
+    tz: &Tz,
+    local: NaiveDateTime,
+    dst: DstPolicy,
+) -> Result<String, ParseError>
+where
+    DateTime<Tz>: TimerAble,
+{
+    match (tz.from_local_datetime(&local), dst) {
+        (LocalResult::Single(date), _) => Ok(date.timer()),
+        (LocalResult::Ambiguous(_, _), DstPolicy::Error) => {
+            Err(ParseError(format!(
+                "{} happens twice because of a daylight saving time \
+                change, use --dst earliest or --dst latest to pick one",
+                local.format("%Y-%m-%d %H:%M:%S")
+            )))
+        }
+        // `Local` returns them in the order of the offsets around the
+        // change, not in time
+        (LocalResult::Ambiguous(a, b), _) => {
+            let (earliest, latest) = if a <= b { (a, b) } else { (b, a) };
+            Ok((if dst == DstPolicy::Earliest {
+                earliest
+            } else {
+                latest
+            })
+            .with_timezone(&Utc)
+            .timer())
+        }
+        (LocalResult::None, DstPolicy::Error) => Err(ParseError(format!(
+            "{} does not exist because of a daylight saving time change, \
+            use --dst earliest or --dst latest to move it",
+            local.format("%Y-%m-%d %H:%M:%S")
+        ))),
+        (LocalResult::None, _) => {
+            // Interpreting the time with the offsets before and after the
+            // gap moves it out of the gap in either direction
+            let around =
+                [local - Duration::days(1), local + Duration::days(1)];
+            let shifted = around.iter().map(|around| {
+                let offset = tz.offset_from_utc_datetime(around).fix();
+                tz.from_utc_datetime(
+                    &(local
+                        - Duration::seconds(
+                            offset.local_minus_utc().into(),
+                        )),
+                )
+            });
+            Ok((if dst == DstPolicy::Earliest {
+                shifted.min()
+            } else {
+                shifted.max()
+            })
+            .unwrap()
+            .timer())
+        }
+    }
+}
+
+/// Computes the local date a relative time specification points to
+trait InAble {
+    fn date<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+    ) -> Result<NaiveDateTime, ParseError>;
+}
+
+impl InAble for In {
+    fn date<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+    ) -> Result<NaiveDateTime, ParseError> {
+        match self {
+            In::Sec(t) => t.date(now),
+            In::Min(t) => t.date(now),
+            In::Hour(t) => t.date(now),
+            In::Day(t) => t.date(now),
+            In::Week(t) => t.date(now),
+            In::Month(t) => t.date(now),
+            In::Year(t) => t.date(now),
+            In::Next(at) => {
+                let now = now.naive_local();
+                let ahead = (at.0.num_days_from_monday() + 6
+                    - now.weekday().num_days_from_monday())
+                    % 7
+                    + 1;
+                at.at(now + Duration::days(ahead.into()))
+            }
+        }
+    }
+}
+
 trait AtAble {
-    fn at(&self, date: DateTime<Local>) -> DateTime<Local>;
+    fn at(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<NaiveDateTime, ParseError>;
+}
+
+/// Applies the optional, more precise specifier of an `At*` or `In*`
+fn and_at<A: AtAble>(
+    at: &Option<A>,
+    date: NaiveDateTime,
+) -> Result<NaiveDateTime, ParseError> {
+    match at {
+        Some(at) => at.at(date),
+        None => Ok(date),
+    }
+}
+
+/// The error for an `in` span that ends after the last representable date
+fn too_far() -> ParseError {
+    ParseError(String::from("the time span is too far in the future"))
+}
+
+fn at_time(
+    date: NaiveDate,
+    hour: u32,
+    minute: u32,
+    second: u32,
+) -> Result<NaiveDateTime, ParseError> {
+    date.and_hms_opt(hour, minute, second).ok_or_else(|| {
+        ParseError(format!(
+            "{:02}:{:02}:{:02} is not a valid time",
+            hour, minute, second
+        ))
+    })
+}
+
+fn number(
+    cap: &regex::Captures,
+    name: &str,
+) -> Result<u32, ParseError> {
+    parse_number(cap.name(name).unwrap().as_str())
+}
+
+fn optional(
+    cap: &regex::Captures,
+    name: &str,
+) -> Result<Option<u32>, ParseError> {
+    cap.name(name).map(|v| parse_number(v.as_str())).transpose()
+}
+
+/// Parses the digits a pattern matched, which may still be too large
+fn parse_number(n: &str) -> Result<u32, ParseError> {
+    u32::from_str(n)
+        .map_err(|_| ParseError(format!("{} is too large", n)))
+}
+
+/// The error for a specifier that doesn't match its pattern
+fn invalid_time(s: &str, what: &str, example: &str) -> ParseError {
+    ParseError(format!(
+        "`{}` is not a valid {}, e.g. use {}",
+        s.trim(),
+        what,
+        example
+    ))
+}
+
+/// Checks each part of a time like `24:60`, pointing at the token and
+/// suggesting the closest valid time
+fn check_time(
+    token: &str,
+    h: Option<u32>,
+    m: Option<u32>,
+    s: Option<u32>,
+) -> Result<(), ParseError> {
+    let parts =
+        [(h, 23, "hour"), (m, 59, "minute"), (s, 59, "second")];
+    match parts.iter().find(|(v, max, _)| v.is_some_and(|v| v > *max))
+    {
+        Some((v, _, name)) => Err(ParseError(format!(
+            "`{}`: {} is not a valid {}, did you mean {}{}?",
+            token.trim(),
+            v.unwrap(),
+            name,
+            if h.is_none() { ":" } else { "" },
+            parts
+                .iter()
+                .filter_map(|(v, max, _)| v
+                    .map(|v| format!("{:02}", v.min(*max))))
+                .collect::<Vec<String>>()
+                .join(":")
+        ))),
+        None => Ok(()),
+    }
 }
 
 pub struct AtSec(u32);
 impl AtAble for AtSec {
-    fn at(&self, date: DateTime<Local>) -> DateTime<Local> {
-        date.with_second(self.0).unwrap()
+    fn at(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<NaiveDateTime, ParseError> {
+        at_time(date.date(), date.hour(), date.minute(), self.0)
     }
 }
 impl Display for AtSec {
@@ -92,34 +365,35 @@ impl FromStr for AtSec {
     fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
         lazy_static! {
             static ref RE: Regex =
-                Regex::new(r":(?P<s>\d\d?)").unwrap();
+                Regex::new(r"^\s*:?(?P<s>\d\d?)\s*$").unwrap();
         }
-        let cap = RE.captures(s).ok_or(ParseError(format!(
-            "`{}` is not a valid seconds specifier.",
-            s
-        )))?;
-        //TODO check if valid number of seconds
-        Ok(Self(
-            u32::from_str(cap.name("s").unwrap().as_str()).unwrap(),
-        ))
+        let cap = RE.captures(s).ok_or_else(|| {
+            invalid_time(s, "seconds specifier", ":30")
+        })?;
+        let sec = number(&cap, "s")?;
+        check_time(s, None, None, Some(sec))?;
+        Ok(Self(sec))
     }
 }
 pub struct InSec(i64);
-impl TimerAble for InSec {
-    fn timer(&self) -> String {
-        (Local::now() + Duration::seconds(self.0)).timer()
+impl InAble for InSec {
+    fn date<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+    ) -> Result<NaiveDateTime, ParseError> {
+        Ok(now
+            .checked_add_signed(Duration::seconds(self.0))
+            .ok_or_else(too_far)?
+            .naive_local())
     }
 }
 pub struct AtMin(u32, Option<AtSec>);
 impl AtAble for AtMin {
-    fn at(&self, date: DateTime<Local>) -> DateTime<Local> {
-        let date =
-            date.with_minute(self.0).unwrap().with_second(0).unwrap();
-        if let Some(at) = &self.1 {
-            at.at(date)
-        } else {
-            date
-        }
+    fn at(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<NaiveDateTime, ParseError> {
+        and_at(&self.1, at_time(date.date(), date.hour(), self.0, 0)?)
     }
 }
 impl Display for AtMin {
@@ -135,52 +409,38 @@ impl FromStr for AtMin {
     fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
         lazy_static! {
             static ref RE: Regex =
-                Regex::new(r":(?P<m>\d\d?)(?P<s>:\d\d?)?").unwrap();
+                Regex::new(r"^\s*:?(?P<m>\d\d?)(:(?P<s>\d\d?))?\s*$")
+                    .unwrap();
         }
-        let cap = RE.captures(s).ok_or(ParseError(format!(
-            "`{}` is not a valid minutes specifier.",
-            s
-        )))?;
-        //TODO check if valid number of seconds
-        Ok(Self(
-            u32::from_str(cap.name("m").unwrap().as_str()).unwrap(),
-            if cap.name("s").is_some() {
-                Some(AtSec::from_str(
-                    cap.name("s").unwrap().as_str(),
-                )?)
-            } else {
-                None
-            },
-        ))
+        let cap = RE.captures(s).ok_or_else(|| {
+            invalid_time(s, "minutes specifier", ":30")
+        })?;
+        let (m, sec) = (number(&cap, "m")?, optional(&cap, "s")?);
+        check_time(s, None, Some(m), sec)?;
+        Ok(Self(m, sec.map(AtSec)))
     }
 }
 pub struct InMin(i64, Option<AtSec>);
-impl TimerAble for InMin {
-    fn timer(&self) -> String {
-        let now = Local::now() + Duration::minutes(self.0);
-        (if let Some(at) = &self.1 {
-            at.at(now)
-        } else {
-            now
-        })
-        .timer()
+impl InAble for InMin {
+    fn date<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+    ) -> Result<NaiveDateTime, ParseError> {
+        and_at(
+            &self.1,
+            now.checked_add_signed(Duration::minutes(self.0))
+                .ok_or_else(too_far)?
+                .naive_local(),
+        )
     }
 }
 pub struct AtHour(u32, Option<AtMin>);
 impl AtAble for AtHour {
-    fn at(&self, date: DateTime<Local>) -> DateTime<Local> {
-        let date = date
-            .with_hour(self.0)
-            .unwrap()
-            .with_minute(0)
-            .unwrap()
-            .with_second(0)
-            .unwrap();
-        if let Some(at) = &self.1 {
-            at.at(date)
-        } else {
-            date
-        }
+    fn at(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<NaiveDateTime, ParseError> {
+        and_at(&self.1, at_time(date.date(), self.0, 0, 0)?)
     }
 }
 impl Display for AtHour {
@@ -195,60 +455,70 @@ impl FromStr for AtHour {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
         lazy_static! {
-            static ref RE: Regex =
-                Regex::new(r"\b(?P<h>\d\d?)(?P<m>:\d\d?(:\d\d?)?)?")
-                    .unwrap();
+            static ref RE: Regex = Regex::new(concat!(
+                r"^\s*(?:(?P<h>\d\d?)(?:[:h](?P<m>\d\d?)(?::(?P<s>\d\d?))?|h)?",
+                r"(?:\s*(?i:(?P<half>[ap])\.?m\.?))?|(?i:(?P<word>noon|midnight)))",
+                r"\s*$"
+            ))
+            .unwrap();
         }
-        let cap = RE.captures(s).ok_or(ParseError(format!(
-            "`{}` is not a valid hours specifier.",
-            s
-        )))?;
-        //TODO check if valid number of seconds
-        Ok(Self(
-            u32::from_str(cap.name("h").unwrap().as_str()).unwrap(),
-            if cap.name("m").is_some() {
-                Some(AtMin::from_str(
-                    cap.name("m").unwrap().as_str(),
-                )?)
+        let cap = RE.captures(s).ok_or_else(|| {
+            invalid_time(s, "time", "16:30, 4pm or 16h30")
+        })?;
+        if let Some(word) = cap.name("word") {
+            let h = if word.as_str().eq_ignore_ascii_case("noon") {
+                12
             } else {
-                None
-            },
-        ))
+                0
+            };
+            return Ok(Self(h, None));
+        }
+        let (h, m, sec) = (
+            number(&cap, "h")?,
+            optional(&cap, "m")?,
+            optional(&cap, "s")?,
+        );
+        // 12am is midnight and 12pm noon
+        let h = match cap.name("half").map(|h| h.as_str()) {
+            Some(_) if h == 0 || h > 12 => Err(ParseError(format!(
+                "`{}`: {} is not a valid hour on a 12 hour clock, did you \
+                mean {:02}:{:02}?",
+                s.trim(),
+                h,
+                h.min(23),
+                m.unwrap_or(0).min(59)
+            )))?,
+            Some(half) if half.eq_ignore_ascii_case("a") => h % 12,
+            Some(_) => h % 12 + 12,
+            None => h,
+        };
+        check_time(s, Some(h), m, sec)?;
+        Ok(Self(h, m.map(|m| AtMin(m, sec.map(AtSec)))))
     }
 }
 pub struct InHour(i64, Option<AtMin>);
-impl TimerAble for InHour {
-    fn timer(&self) -> String {
-        let now = Local::now() + Duration::hours(self.0);
-        (if let Some(at) = &self.1 {
-            at.at(now)
-        } else {
-            now
-        })
-        .timer()
+impl InAble for InHour {
+    fn date<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+    ) -> Result<NaiveDateTime, ParseError> {
+        and_at(
+            &self.1,
+            now.checked_add_signed(Duration::hours(self.0))
+                .ok_or_else(too_far)?
+                .naive_local(),
+        )
     }
 }
-pub struct AtDay(u32, Option<AtHour>);
+pub struct AtDay(i32, Option<AtHour>);
 impl AtAble for AtDay {
-    fn at(&self, date: DateTime<Local>) -> DateTime<Local> {
-        let date = unwrap!(
-            date.with_day(self.0),
-            "The {}-{}-{} does not exist.",
-            date.year(),
-            date.month(),
-            self.0
-        )
-        .with_hour(0)
-        .unwrap()
-        .with_minute(0)
-        .unwrap()
-        .with_second(0)
-        .unwrap();
-        if let Some(at) = &self.1 {
-            at.at(date)
-        } else {
-            date
-        }
+    fn at(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<NaiveDateTime, ParseError> {
+        let day =
+            month::day_of_month(date.year(), date.month(), self.0)?;
+        and_at(&self.1, at_time(day, 0, 0, 0)?)
     }
 }
 impl Display for AtDay {
@@ -266,59 +536,67 @@ impl FromStr for AtDay {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
         lazy_static! {
-            static ref RE: Regex = Regex::new(
-                r"(?P<d>\d\d?).?\s*\b(?P<h>\d\d?(:\d\d?(:\d\d?)?)?)?"
-            )
+            static ref RE: Regex = Regex::new(concat!(
+                r"^\s*(?P<d>-?\d\d?|last)\.?\s*(?P<h>",
+                time_pattern!(),
+                r")?\s*$"
+            ))
             .unwrap();
         }
-        let cap = RE.captures(s).ok_or(ParseError(format!(
-            "`{}` is not a valid day specifier.",
-            s
-        )))?;
-        //TODO check if valid number of days
+        let cap = RE.captures(s).ok_or_else(|| {
+            invalid_time(s, "day specifier", "15. 9:00")
+        })?;
+        let day = match cap.name("d").unwrap().as_str() {
+            "last" => -1,
+            d => i32::from_str(d).unwrap(),
+        };
+        if day == 0 || !(-31..=31).contains(&day) {
+            Err(ParseError(format!(
+                "`{}`: {} is not a valid day of the month, did you mean {}?",
+                s.trim(),
+                day,
+                match day {
+                    0 => 1,
+                    d if d > 31 => 31,
+                    _ => -31,
+                }
+            )))?;
+        }
         Ok(Self(
-            u32::from_str(cap.name("d").unwrap().as_str()).unwrap(),
-            if cap.name("h").is_some() {
-                Some(AtHour::from_str(
-                    cap.name("h").unwrap().as_str(),
-                )?)
-            } else {
-                None
+            day,
+            match cap.name("h") {
+                Some(h) => Some(AtHour::from_str(h.as_str())?),
+                None => None,
             },
         ))
     }
 }
 pub struct InDay(i64, Option<AtHour>);
-impl TimerAble for InDay {
-    fn timer(&self) -> String {
-        let now = Local::now() + Duration::days(self.0);
-        (if let Some(at) = &self.1 {
-            at.at(now)
-        } else {
-            now
-        })
-        .timer()
+impl InAble for InDay {
+    fn date<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+    ) -> Result<NaiveDateTime, ParseError> {
+        and_at(
+            &self.1,
+            now.naive_local()
+                .checked_add_signed(Duration::days(self.0))
+                .ok_or_else(too_far)?,
+        )
     }
 }
 pub struct AtWeekDay(Weekday, Option<AtHour>);
 impl AtAble for AtWeekDay {
-    fn at(&self, date: DateTime<Local>) -> DateTime<Local> {
-        let date = (date
+    fn at(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<NaiveDateTime, ParseError> {
+        let day = date.date()
             - Duration::days(
-                date.weekday().number_from_monday().into(),
+                date.weekday().num_days_from_monday().into(),
             )
-            + Duration::days(self.0.number_from_monday().into()))
-        .with_hour(0)
-        .unwrap()
-        .with_minute(0)
-        .unwrap()
-        .with_second(0)
-        .unwrap();
-        if let Some(at) = &self.1 {
-            at.at(date)
-        } else {
-            date
-        }
+            + Duration::days(self.0.num_days_from_monday().into());
+        and_at(&self.1, at_time(day, 0, 0, 0)?)
     }
 }
 impl Display for AtWeekDay {
@@ -333,45 +611,44 @@ impl FromStr for AtWeekDay {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
         lazy_static! {
-            static ref RE: Regex = Regex::new(
-                r"(?P<d>[A-Za-z]*)\s*(?P<h>\d\d?(:\d\d?(:\d\d?)?)?)?"
-            )
+            static ref RE: Regex = Regex::new(concat!(
+                r"^\s*(?P<d>[A-Za-z]+)\.?\s*(?P<h>",
+                time_pattern!(),
+                r")?\s*$"
+            ))
             .unwrap();
         }
-        let cap = RE.captures(s).ok_or(ParseError(format!(
-            "`{}` is not a valid weekday specifier.",
-            s
-        )))?;
-        //
+        let cap = RE.captures(s).ok_or_else(|| {
+            invalid_time(s, "weekday specifier", "Fri 9:00")
+        })?;
         Ok(Self(
-            Weekday::from_str(cap.name("d").unwrap().as_str())
-                .or_else(|_| {
-                    Err(ParseError(format!(
+            names::weekday(cap.name("d").unwrap().as_str())
+                .ok_or_else(|| {
+                    ParseError(format!(
                         "`{}` is not a valid Weekday",
                         cap.name("d").unwrap().as_str()
-                    )))
+                    ))
                 })?,
-            if cap.name("h").is_some() {
-                Some(AtHour::from_str(
-                    cap.name("h").unwrap().as_str(),
-                )?)
-            } else {
-                None
+            match cap.name("h") {
+                Some(h) => Some(AtHour::from_str(h.as_str())?),
+                None => None,
             },
         ))
     }
 }
 
 pub struct InWeek(i64, Option<WeekSub>);
-impl TimerAble for InWeek {
-    fn timer(&self) -> String {
-        let now = Local::now() + Duration::weeks(self.0);
-        (if let Some(at) = &self.1 {
-            at.at(now)
-        } else {
-            now
-        })
-        .timer()
+impl InAble for InWeek {
+    fn date<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+    ) -> Result<NaiveDateTime, ParseError> {
+        and_at(
+            &self.1,
+            now.naive_local()
+                .checked_add_signed(Duration::weeks(self.0))
+                .ok_or_else(too_far)?,
+        )
     }
 }
 
@@ -380,7 +657,10 @@ pub enum WeekSub {
     AtWeekDay(AtWeekDay),
 }
 impl AtAble for WeekSub {
-    fn at(&self, date: DateTime<Local>) -> DateTime<Local> {
+    fn at(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<NaiveDateTime, ParseError> {
         match self {
             Self::AtHour(at) => at.at(date),
             Self::AtWeekDay(at) => at.at(date),
@@ -417,64 +697,17 @@ impl FromStr for WeekSub {
 
 pub struct AtNthWeekDay(i8, Weekday, Option<AtHour>);
 impl AtAble for AtNthWeekDay {
-    fn at(&self, date: DateTime<Local>) -> DateTime<Local> {
-        let wanted = if self.0 > 0 {
-            let tmp = date.with_day(1).unwrap();
-            (if tmp.weekday().number_from_monday()
-                > self.1.number_from_monday()
-            {
-                self.1.number_from_monday() + 7
-                    - tmp.weekday().number_from_monday()
-            } else {
-                self.1.number_from_monday()
-                    - tmp.weekday().number_from_monday()
-            }) + (7 * self.0) as u32
-        } else {
-            let tmp =
-                date.with_day(get_days_from_month(date) - 1).unwrap();
-            let wanted: i32 = (if tmp.weekday().number_from_monday()
-                < self.1.number_from_monday()
-            {
-                self.1.number_from_monday()
-                    - 7
-                    - tmp.weekday().number_from_monday()
-            } else {
-                self.1.number_from_monday()
-                    - tmp.weekday().number_from_monday()
-            }) as i32
-                + (7 * self.0) as i32
-                + tmp.day() as i32;
-            if wanted > 0 {
-                wanted as u32
-            } else {
-                panic!(
-                    "{}-{} does not have {} {}s",
-                    date.year(),
-                    date.month(),
-                    -self.0,
-                    self.1
-                )
-            }
-        };
-        let date = unwrap!(
-            date.with_day(wanted),
-            "{}-{} does not have {} {}s",
+    fn at(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<NaiveDateTime, ParseError> {
+        let day = month::nth_weekday(
             date.year(),
             date.month(),
             self.0,
-            self.1
-        )
-        .with_hour(0)
-        .unwrap()
-        .with_minute(0)
-        .unwrap()
-        .with_second(0)
-        .unwrap();
-        if let Some(at) = &self.2 {
-            at.at(date)
-        } else {
-            date
-        }
+            self.1,
+        )?;
+        and_at(&self.2, at_time(day, 0, 0, 0)?)
     }
 }
 impl Display for AtNthWeekDay {
@@ -493,42 +726,74 @@ impl FromStr for AtNthWeekDay {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
         lazy_static! {
-            static ref RE: Regex = Regex::new(
-                r"(?P<n>-?\d).?\w*(?P<d>[A-Za-z]*})\s*(?P<h>\d\d?(:\d\d?(:\d\d?)?)?)?"
-            )
+            static ref RE: Regex = Regex::new(concat!(
+                r"(?i)^\s*(?P<n>-?\d|first|second|third|fourth|fifth|last)",
+                r"(?:\.|st|nd|rd|th)?(?P<to_last>[\s-]+to[\s-]+last)?",
+                r"\s+(?P<d>[A-Za-z]+)\.?\s*(?P<h>",
+                time_pattern!(),
+                r")?\s*$"
+            ))
             .unwrap();
         }
-        let cap = RE.captures(s).ok_or(ParseError(format!(
-            "`{}` is not a valid nth weekday specifier.",
-            s
-        )))?;
-        //
+        let cap = RE.captures(s).ok_or_else(|| {
+            invalid_time(
+                s,
+                "nth weekday specifier",
+                "2nd Tue or last Fri",
+            )
+        })?;
+        let n = cap.name("n").unwrap().as_str().to_lowercase();
+        let n = match n.as_str() {
+            "first" => 1,
+            "second" => 2,
+            "third" => 3,
+            "fourth" => 4,
+            "fifth" => 5,
+            "last" if cap.name("to_last").is_none() => -1,
+            "last" => Err(ParseError(format!(
+                "`{}` is not a valid nth weekday specifier, e.g. use \
+                second to last Sun",
+                s.trim()
+            )))?,
+            n => i8::from_str(n).unwrap(),
+        };
+        let n = if cap.name("to_last").is_some() { -n } else { n };
+        if n == 0 || !(-5..=5).contains(&n) {
+            Err(ParseError(format!(
+                "`{}`: {} is not valid, a weekday occurs at most 5 times a \
+                month",
+                s.trim(),
+                n
+            )))?;
+        }
         Ok(Self(
-            i8::from_str(cap.name("n").unwrap().as_str()).unwrap(),
-            Weekday::from_str(cap.name("d").unwrap().as_str()).or(
-                Err(ParseError(format!(
-                    "`{}` is not a valid Weekday",
-                    cap.name("d").unwrap().as_str()
-                ))),
-            )?,
-            if cap.name("h").is_some() {
-                Some(AtHour::from_str(
-                    cap.name("h").unwrap().as_str(),
-                )?)
-            } else {
-                None
+            n,
+            names::weekday(cap.name("d").unwrap().as_str())
+                .ok_or_else(|| {
+                    ParseError(format!(
+                        "`{}` is not a valid Weekday",
+                        cap.name("d").unwrap().as_str()
+                    ))
+                })?,
+            match cap.name("h") {
+                Some(h) => Some(AtHour::from_str(h.as_str())?),
+                None => None,
             },
         ))
     }
 }
 
+#[allow(clippy::enum_variant_names)]
 pub enum MonthSub {
     AtDay(AtDay),
     AtNthWeekDay(AtNthWeekDay),
     AtHour(AtHour),
 }
 impl AtAble for MonthSub {
-    fn at(&self, date: DateTime<Local>) -> DateTime<Local> {
+    fn at(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<NaiveDateTime, ParseError> {
         match self {
             Self::AtDay(at) => at.at(date),
             Self::AtNthWeekDay(at) => at.at(date),
@@ -551,7 +816,23 @@ impl Display for MonthSub {
 impl FromStr for MonthSub {
     type Err = ParseError;
     fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
+        lazy_static! {
+            // A bare number is an hour, days need a dot, a following time
+            // or a sign, e.g. `31.`, `31 9:00`, `-1` or `last`
+            static ref DAY: Regex =
+                Regex::new(r"^\s*(-\d\d?|\d\d?(\.|\s+\d)|last\b)").unwrap();
+        }
         let nwd = AtNthWeekDay::from_str(s);
+        if nwd.is_err() && DAY.is_match(s) {
+            return AtDay::from_str(s).map(Self::AtDay);
+        }
+        // Only an nth weekday names a weekday
+        if nwd.is_err()
+            && s.split_whitespace()
+                .any(|w| names::weekday(w).is_some())
+        {
+            return nwd.map(Self::AtNthWeekDay);
+        }
         if let Err(nwde) = nwd {
             let h = AtHour::from_str(s);
             if let Err(he) = h {
@@ -574,56 +855,1366 @@ impl FromStr for MonthSub {
 }
 
 pub struct InMonth(u32, Option<MonthSub>);
-impl TimerAble for InMonth {
-    fn timer(&self) -> String {
-        let now = Local::now();
-        let year = ((self.0 + now.month()) / 12) as i32 + now.year();
-        let month = (self.0 + now.month()) % 12;
-
-        let mut wanted = now
-            .with_day(1)
-            .unwrap()
-            .with_year(year)
-            .unwrap()
-            .with_month(month)
-            .unwrap();
-        wanted = wanted
-            .with_day(now.day().min(get_days_from_month(wanted)))
-            .unwrap();
-
-        (if let Some(at) = &self.1 {
-            at.at(wanted)
-        } else {
-            wanted
-        })
-        .timer()
+impl InAble for InMonth {
+    fn date<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+    ) -> Result<NaiveDateTime, ParseError> {
+        let now = now.naive_local();
+        let day = month::add_months(now.date(), self.0.into())?;
+        and_at(&self.1, day.and_time(now.time()))
     }
 }
 
-#[derive(Debug)]
-#[allow(non_snake_case)]
-pub struct Calendar {
-    wd: IntRestriction,
-    d: IntRestriction,
-    M: IntRestriction,
-    y: IntRestriction,
-    s: IntRestriction,
-    m: IntRestriction,
-    h: IntRestriction,
+pub struct AtMonthDay(u32, u32, Option<AtHour>);
+impl AtAble for AtMonthDay {
+    fn at(
+        &self,
+        date: NaiveDateTime,
+    ) -> Result<NaiveDateTime, ParseError> {
+        let day = month::month_day(date.year(), self.0, self.1)?;
+        and_at(&self.2, at_time(day, 0, 0, 0)?)
+    }
 }
-
-#[derive(Debug)]
-pub enum IntRestriction {
-    Restrictions(IntRange),
-    NoRestriction,
+impl Display for AtMonthDay {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AtMonthDay(m, d, None) => {
+                write!(f, "{:02}-{:02} 00:00:00", m, d)
+            }
+            AtMonthDay(m, d, Some(a)) => {
+                write!(f, "{:02}-{:02} {}", m, d, a)
+            }
+        }
+    }
 }
-
-#[derive(Debug)]
-pub struct IntRange(i32, i32);
-
-#[allow(non_snake_case)]
-pub struct InDuration {
-    µs: Option<f64>,
+impl FromStr for AtMonthDay {
+    type Err = ParseError;
+    fn from_str(s: &str) -> Result<Self, <Self as FromStr>::Err> {
+        lazy_static! {
+            static ref RE: Regex = Regex::new(concat!(
+                r"^\s*((?P<M>\d\d?)-(?P<d>\d\d?)",
+                r"|(?P<name>\p{L}+)\.?\s*(?P<nd>\d\d?)(st|nd|rd|th)?\.?)",
+                r"\s*(?P<h>",
+                time_pattern!(),
+                r")?\s*$"
+            ))
+            .unwrap();
+        }
+        let cap = RE.captures(s).ok_or(ParseError(format!(
+            "`{}` is not a valid month and day specifier, e.g. use 05-01 \
+            or Jan 1st",
+            s
+        )))?;
+        let (month, day) = match cap.name("name") {
+            Some(name) => (
+                names::month(name.as_str()).ok_or_else(|| {
+                    ParseError(format!(
+                        "`{}` is not a valid month",
+                        name.as_str()
+                    ))
+                })?,
+                cap.name("nd").unwrap().as_str(),
+            ),
+            None => (
+                u32::from_str(cap.name("M").unwrap().as_str())
+                    .unwrap(),
+                cap.name("d").unwrap().as_str(),
+            ),
+        };
+        // Checked against a leap year, so Feb 29 is only rejected once the
+        // actual year is known
+        month::month_day(2000, month, u32::from_str(day).unwrap())?;
+        Ok(Self(
+            month,
+            u32::from_str(day).unwrap(),
+            if cap.name("h").is_some() {
+                Some(AtHour::from_str(
+                    cap.name("h").unwrap().as_str(),
+                )?)
+            } else {
+                None
+            },
+        ))
+    }
+}
+
+pub struct InYear(u32, Option<AtMonthDay>);
+impl InAble for InYear {
+    fn date<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+    ) -> Result<NaiveDateTime, ParseError> {
+        let now = now.naive_local();
+        let day =
+            month::add_months(now.date(), i64::from(self.0) * 12)?;
+        match &self.1 {
+            // Like adding years to Feb 29, Feb 29 of a year without one is
+            // clamped to Feb 28
+            Some(AtMonthDay(2, 29, at))
+                if month::month_day(day.year(), 2, 29).is_err() =>
+            {
+                and_at(
+                    at,
+                    at_time(
+                        month::month_day(day.year(), 2, 28)?,
+                        0,
+                        0,
+                        0,
+                    )?,
+                )
+            }
+            at => and_at(at, day.and_time(now.time())),
+        }
+    }
+}
+
+/// A fixed point in time like `2026-11-03 14:00`, `@1793714400` or
+/// `on Nov 3 at 14:00`
+pub enum Absolute {
+    /// A wall clock time
+    Local(NaiveDateTime),
+    /// An exact instant, from an epoch or a time with UTC offset
+    Instant(DateTime<Utc>),
+    /// The next time a day of the year comes around
+    Next(AtMonthDay),
+}
+
+impl Debug for Absolute {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        match self {
+            Absolute::Local(date) => {
+                write!(f, "at {}", date.format("%Y-%m-%d %H:%M:%S"))
+            }
+            Absolute::Instant(instant) => write!(
+                f,
+                "at {} UTC",
+                instant.format("%Y-%m-%d %H:%M:%S")
+            ),
+            Absolute::Next(a) => write!(f, "on {}", a),
+        }
+    }
+}
+
+impl InAble for Absolute {
+    fn date<Tz: TimeZone>(
+        &self,
+        now: DateTime<Tz>,
+    ) -> Result<NaiveDateTime, ParseError> {
+        match self {
+            Absolute::Local(date) => Ok(*date),
+            Absolute::Instant(instant) => Ok(instant
+                .with_timezone(&now.timezone())
+                .naive_local()),
+            Absolute::Next(at) => {
+                let now = now.naive_local();
+                // Feb 29 can be up to 8 years away
+                (0..=8)
+                    .filter_map(|years| {
+                        NaiveDate::from_ymd_opt(
+                            now.year() + years,
+                            1,
+                            1,
+                        )
+                    })
+                    .filter_map(|year| {
+                        at.at(year.and_hms_opt(0, 0, 0)?).ok()
+                    })
+                    .find(|date| *date > now)
+                    .ok_or_else(|| {
+                        ParseError(format!(
+                            "{} never comes around",
+                            at
+                        ))
+                    })
+            }
+        }
+    }
+}
+
+impl FromStr for Absolute {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref ISO: Regex = Regex::new(concat!(
+                r"(?i)^\s*((at|on)\s+)?",
+                r"(?P<y>\d{4})-(?P<M>\d\d?)-(?P<d>\d\d?)",
+                r"((\s+at\s+|\s+|T)(?P<time>",
+                time_pattern!(),
+                r"))?",
+                r"(?P<offset>Z|(?P<sign>[+-])(?P<oh>\d\d):?(?P<om>\d\d))?\s*$"
+            ))
+            .unwrap();
+            static ref EPOCH: Regex =
+                Regex::new(r"^\s*@(?P<s>\d+)\s*$").unwrap();
+            static ref ON: Regex = Regex::new(concat!(
+                r"(?i)^\s*on\s+",
+                r"(?P<date>\p{L}+\.?\s*\d\d?(st|nd|rd|th)?\.?|\d\d?-\d\d?)",
+                r"(\s+(?P<y>\d{4}))?(\s+at\s+(?P<time>.*?))?\s*$"
+            ))
+            .unwrap();
+        }
+        let number = |cap: &regex::Captures, name| {
+            u32::from_str(cap.name(name).unwrap().as_str()).unwrap()
+        };
+
+        if let Some(cap) = EPOCH.captures(s) {
+            let secs = cap.name("s").unwrap().as_str();
+            return i64::from_str(secs)
+                .ok()
+                .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+                .map(Absolute::Instant)
+                .ok_or_else(|| {
+                    ParseError(format!("{} is too large", secs))
+                });
+        }
+
+        if let Some(cap) = ISO.captures(s) {
+            let date = month::month_day(
+                number(&cap, "y") as i32,
+                number(&cap, "M"),
+                number(&cap, "d"),
+            )?;
+            let time = match cap.name("time") {
+                Some(time) => time_of_day(time.as_str())?,
+                None => NaiveTime::from_hms_opt(0, 0, 0).unwrap(),
+            };
+            let local = date.and_time(time);
+            return match cap.name("offset") {
+                None => Ok(Absolute::Local(local)),
+                Some(_) if cap.name("sign").is_none() => Ok(
+                    Absolute::Instant(Utc.from_utc_datetime(&local)),
+                ),
+                Some(offset) => {
+                    let secs = (number(&cap, "oh") * 60
+                        + number(&cap, "om"))
+                        * 60;
+                    match cap.name("sign").unwrap().as_str() {
+                        "-" => FixedOffset::west_opt(secs as i32),
+                        _ => FixedOffset::east_opt(secs as i32),
+                    }
+                    .and_then(|o| {
+                        o.from_local_datetime(&local).single()
+                    })
+                    .map(|date| {
+                        Absolute::Instant(date.with_timezone(&Utc))
+                    })
+                    .ok_or_else(|| {
+                        ParseError(format!(
+                            "`{}` is not a valid UTC offset",
+                            offset.as_str()
+                        ))
+                    })
+                }
+            };
+        }
+
+        let cap = ON.captures(s).ok_or_else(|| {
+            ParseError(format!(
+                "`{}` is not a valid date, e.g. use 2026-11-03 14:00, \
+                @1793714400 or on Nov 3 at 14:00",
+                s
+            ))
+        })?;
+        let AtMonthDay(month, day, _) =
+            AtMonthDay::from_str(cap.name("date").unwrap().as_str())?;
+        let at = match cap.name("time") {
+            Some(time) => Some(AtHour::from_str(time.as_str())?),
+            None => None,
+        };
+        match cap.name("y") {
+            Some(_) => Ok(Absolute::Local(and_at(
+                &at,
+                at_time(
+                    month::month_day(
+                        number(&cap, "y") as i32,
+                        month,
+                        day,
+                    )?,
+                    0,
+                    0,
+                    0,
+                )?,
+            )?)),
+            None => Ok(Absolute::Next(AtMonthDay(month, day, at))),
+        }
+    }
+}
+
+/// A repeating schedule like `every 15m from 09:00 to 17:00 on Mon..Fri`
+/// or `every day at 8 until 2026-12-31`
+///
+/// Systemd timers have no end date, so `until` restricts the calendar to
+/// the dates up to it, afterwards the timer never elapses again.
+#[derive(Debug)]
+pub struct Every {
+    interval: u32,
+    unit: Unit,
+    at: Option<NaiveTime>,
+    window: Option<(NaiveTime, NaiveTime)>,
+    days: Vec<(Weekday, Option<Weekday>)>,
+    /// The day of the month for monthly intervals, negative counts from
+    /// the end
+    day: Option<i32>,
+    /// Which occurrence of the weekday in `days` in the month, negative
+    /// counts from the end
+    nth: Option<i8>,
+    /// The months to run in, like `in Jan-Mar`
+    months: IntRestriction,
+    until: Option<NaiveDate>,
+}
+
+impl Every {
+    /// The `OnCalendar=` lines, `today` is used to start the `until`
+    /// restriction
+    fn timer(
+        &self,
+        today: NaiveDate,
+        zone: Option<&Tz>,
+    ) -> Result<String, ParseError> {
+        let days = match (&self.unit, self.days.is_empty()) {
+            (Unit::w, true) => String::from("Mon "),
+            (_, true) => String::new(),
+            _ => format!(
+                "{} ",
+                self.days
+                    .iter()
+                    .map(|(from, to)| match to {
+                        Some(to) => format!("{}..{}", from, to),
+                        None => from.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+        };
+        let dates = match &self.until {
+            Some(until) => until_dates(today, *until, &self.months)?,
+            None => vec![self.date()?],
+        };
+        let times = self.times()?;
+        let zone =
+            zone.map(|z| format!(" {}", z)).unwrap_or_default();
+        Ok(dates
+            .iter()
+            .flat_map(|date| {
+                times.iter().map(move |time| (date, time))
+            })
+            .map(|(date, time)| {
+                format!(
+                    "OnCalendar={}{} {}{}",
+                    days, date, time, zone
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n"))
+    }
+
+    /// Errors if `until` is before `today`, the timer would never elapse
+    fn check_future(
+        &self,
+        today: NaiveDate,
+    ) -> Result<(), ParseError> {
+        match self.until {
+            Some(until) if until < today => Err(ParseError(format!(
+                "{} is in the past, use --allow-past to create the timer \
+                anyway",
+                until
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    fn date(&self) -> Result<String, ParseError> {
+        Ok(match self.unit {
+            Unit::M => {
+                let months = match self.months {
+                    IntRestriction::NoRestriction => {
+                        repeat(1, self.interval, 12)?
+                    }
+                    _ => self.months.to_string(),
+                };
+                // The nth weekday is in the nth week of the month
+                let week = |n: i8| {
+                    let n = u32::from(n.unsigned_abs());
+                    span((n - 1) * 7 + 1, (n * 7).min(31))
+                };
+                match (self.nth, self.day) {
+                    (Some(n), _) if n > 0 => {
+                        format!("*-{}-{}", months, week(n))
+                    }
+                    (Some(n), _) => {
+                        format!("*-{}~{}", months, week(n))
+                    }
+                    (None, Some(day)) if day < 0 => {
+                        format!("*-{}~{:02}", months, -day)
+                    }
+                    (None, day) => format!(
+                        "*-{}-{:02}",
+                        months,
+                        day.unwrap_or(1)
+                    ),
+                }
+            }
+            Unit::y if self.interval == 1 => String::from("*-01-01"),
+            Unit::y => format!(
+                "{}/{}-01-01",
+                Local::now().year(),
+                self.interval
+            ),
+            _ => format!("*-{}-*", self.months),
+        })
+    }
+
+    fn times(&self) -> Result<Vec<String>, ParseError> {
+        let n = self.interval;
+        Ok(match (&self.unit, &self.window) {
+            (Unit::s, None) => vec![format!("*:*:{}", repeat(0, n, 60)?)],
+            (Unit::m, None) => {
+                vec![format!("*:{}:00", repeat(0, n, 60)?)]
+            }
+            (Unit::h, None) => {
+                vec![format!("{}:00:00", repeat(0, n, 24)?)]
+            }
+            (Unit::s, Some(_)) => Err(ParseError(String::from(
+                "from and to can't be combined with seconds, use an \
+                interval in minutes or hours",
+            )))?,
+            (Unit::m, Some((from, to))) => {
+                repeat(0, n, 60)?;
+                minute_window(*from, *to, n)
+            }
+            (Unit::h, Some((from, to))) => {
+                repeat(0, n, 24)?;
+                let mut to_hour = to.hour();
+                if to.minute() < from.minute() {
+                    to_hour -= 1;
+                }
+                vec![format!(
+                    "{}:{:02}:00",
+                    steps(from.hour(), to_hour, n, 24),
+                    from.minute()
+                )]
+            }
+            (_, Some(_)) => Err(ParseError(String::from(
+                "from and to only apply to intervals shorter than a day, \
+                use at to pick the time",
+            )))?,
+            (Unit::d, None) | (Unit::w, None) if n != 1 => {
+                Err(ParseError(format!(
+                    "every {}{} can't be expressed as a calendar, as \
+                    systemd restarts the repetition every month",
+                    n, self.unit
+                )))?
+            }
+            _ => vec![self
+                .at
+                .unwrap_or_else(|| NaiveTime::from_hms_opt(0, 0, 0).unwrap())
+                .format("%H:%M:%S")
+                .to_string()],
+        })
+    }
+}
+
+/// `from/step` when `step` evenly divides `max`, in which case the
+/// repetition lines up when systemd restarts it
+fn repeat(
+    from: u32,
+    step: u32,
+    max: u32,
+) -> Result<String, ParseError> {
+    if step == 0 || !max.is_multiple_of(step) {
+        Err(ParseError(format!(
+            "{} does not evenly divide {}, so it can't be expressed as a \
+            calendar",
+            step, max
+        )))
+    } else if step == 1 {
+        Ok(String::from("*"))
+    } else {
+        Ok(format!("{:02}/{}", from, step))
+    }
+}
+
+/// The values from `from` to `to` in steps of `step`, as `from/step` when
+/// the steps run up to `max` anyway
+fn steps(from: u32, to: u32, step: u32, max: u32) -> String {
+    let last = to - (to - from) % step;
+    if last + step >= max {
+        format!("{:02}/{}", from, step)
+    } else if step == 1 || last == from {
+        span(from, last)
+    } else {
+        format!("{:02}..{:02}/{}", from, last, step)
+    }
+}
+
+/// `from` as a single value or a range to `to`
+fn span(from: u32, to: u32) -> String {
+    if from == to {
+        format!("{:02}", from)
+    } else {
+        format!("{:02}..{:02}", from, to)
+    }
+}
+
+/// Every `step` minutes from `from` to `to`, split into the partial first
+/// and last hour and the full hours between
+fn minute_window(
+    from: NaiveTime,
+    to: NaiveTime,
+    step: u32,
+) -> Vec<String> {
+    let (first, last) = (from.hour(), to.hour());
+    let offset = from.minute() % step;
+    let mut times = Vec::new();
+    if first == last {
+        times.push(format!(
+            "{:02}:{}:00",
+            first,
+            steps(from.minute(), to.minute(), step, 60)
+        ));
+        return times;
+    }
+    times.push(format!(
+        "{:02}:{}:00",
+        first,
+        steps(from.minute(), 59, step, 60)
+    ));
+    if last > first + 1 {
+        times.push(format!(
+            "{}:{}:00",
+            span(first + 1, last - 1),
+            steps(offset, 59, step, 60)
+        ));
+    }
+    if to.minute() >= offset {
+        times.push(format!(
+            "{:02}:{}:00",
+            last,
+            steps(offset, to.minute(), step, 60)
+        ));
+    }
+    times
+}
+
+/// The months from `from` to `to` that `months` allows, `None` if there
+/// are none
+fn month_spans(
+    months: &IntRestriction,
+    from: u32,
+    to: u32,
+) -> Option<String> {
+    let mut spans: Vec<(u32, u32)> = Vec::new();
+    for month in (from..=to).filter(|m| months.matches(*m as i32)) {
+        match spans.last_mut() {
+            Some((_, last)) if *last + 1 == month => *last = month,
+            _ => spans.push((month, month)),
+        }
+    }
+    match spans.as_slice() {
+        [] => None,
+        [(1, 12)] => Some(String::from("*")),
+        spans => Some(
+            spans
+                .iter()
+                .map(|(from, to)| span(*from, *to))
+                .collect::<Vec<String>>()
+                .join(","),
+        ),
+    }
+}
+
+/// The date restrictions covering `today` up to and including `until`, in
+/// the `months` the schedule runs in, an `until` in the past covers its
+/// year up to it
+fn until_dates(
+    today: NaiveDate,
+    until: NaiveDate,
+    months_allowed: &IntRestriction,
+) -> Result<Vec<String>, ParseError> {
+    let today = if until < today {
+        NaiveDate::from_ymd_opt(until.year(), 1, 1).unwrap()
+    } else {
+        today
+    };
+    let (year, month, day) =
+        (until.year(), until.month(), until.day());
+    let whole_month = month::days_in_month(year, month)? == day;
+    let months = if whole_month { month } else { month - 1 };
+    let years = |to: i32| {
+        if today.year() == to {
+            to.to_string()
+        } else {
+            format!("{}..{}", today.year(), to)
+        }
+    };
+    let mut dates = Vec::new();
+    if months == 12 {
+        dates.extend(
+            month_spans(months_allowed, 1, 12)
+                .map(|m| format!("{}-{}-*", years(year), m)),
+        );
+    } else {
+        if year > today.year() {
+            dates.extend(
+                month_spans(months_allowed, 1, 12)
+                    .map(|m| format!("{}-{}-*", years(year - 1), m)),
+            );
+        }
+        if months > 0 {
+            dates.extend(
+                month_spans(months_allowed, 1, months)
+                    .map(|m| format!("{}-{}-*", year, m)),
+            );
+        }
+        if !whole_month && months_allowed.matches(month as i32) {
+            dates.push(format!(
+                "{}-{:02}-{}",
+                year,
+                month,
+                span(1, day)
+            ));
+        }
+    }
+    // Months of this year before today would never elapse again
+    let runs = (today.year()..=year)
+        .flat_map(|y| (1..=12).map(move |m| (y, m)))
+        .filter(|ym| {
+            (today.year(), today.month()) <= *ym
+                && *ym <= (year, month)
+        })
+        .any(|(_, m)| months_allowed.matches(m as i32));
+    if !runs {
+        Err(ParseError(format!(
+            "the timer never runs in the months up to {}",
+            until
+        )))?;
+    }
+    Ok(dates)
+}
+
+/// Parses a day of the month like `15` or `last`, negative days count from
+/// the end
+fn month_day(s: &str) -> Result<i32, ParseError> {
+    match s {
+        _ if s.eq_ignore_ascii_case("last") => Ok(-1),
+        _ => i32::from_str(s)
+            .ok()
+            .filter(|d| *d != 0 && (-31..=31).contains(d))
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "`{}` is not a day of the month, e.g. use 15 or last",
+                    s
+                ))
+            }),
+    }
+}
+
+/// Shorthands from the README and the schedule they stand for, these are
+/// case sensitive as `mly` and `Mly` differ
+const SHORTHANDS: [(&str, &str); 6] = [
+    ("mly", "every minute"),
+    ("hly", "every hour"),
+    ("dly", "every day"),
+    ("wly", "every week"),
+    ("Mly", "every month"),
+    ("yly", "every year"),
+];
+
+/// Words that stand for a schedule, matched in any case
+const ALIASES: [(&str, &str); 13] = [
+    ("minutely", "every minute"),
+    ("hourly", "every hour"),
+    ("daily", "every day"),
+    ("weekly", "every week"),
+    ("monthly", "every month"),
+    ("yearly", "every year"),
+    ("annually", "every year"),
+    ("quarterly", "every 3 months"),
+    ("semiannually", "every 6 months"),
+    ("weekdays", "every day on Mon..Fri"),
+    ("weekends", "every day on Sat,Sun"),
+    ("tomorrow", "in 1d"),
+    ("end of month", "every month on last"),
+];
+
+/// Rewrites shorthands like `daily at 8`, `tomorrow` or `noon` into the
+/// `every` and `in` grammar, a bare time runs every day like in systemd
+fn expand(spec: &str) -> String {
+    lazy_static! {
+        static ref TIME_WORD: Regex =
+            Regex::new(r"(?i)\b(noon|midnight)\b").unwrap();
+        static ref TIME: Regex =
+            Regex::new(concat!(
+                r"(?i)^\s*(at\s+)?(?P<t>",
+                time_pattern!(),
+                r")\s*$"
+            ))
+                .unwrap();
+        static ref WORD: Regex = Regex::new(
+            r"^\s*(?P<word>(?i:end\s+of\s+month)|[A-Za-z]+)(?P<rest>\s.*)?$"
+        )
+        .unwrap();
+    }
+    let spec =
+        TIME_WORD.replace_all(spec, |cap: &regex::Captures| {
+            if cap[1].eq_ignore_ascii_case("noon") {
+                "12:00"
+            } else {
+                "00:00"
+            }
+        });
+    if let Some(cap) = TIME.captures(&spec) {
+        return format!("every day at {}", &cap["t"]);
+    }
+    let cap = match WORD.captures(&spec) {
+        Some(cap) => cap,
+        None => return spec.into_owned(),
+    };
+    let word =
+        cap["word"].split_whitespace().collect::<Vec<_>>().join(" ");
+    let rest = cap.name("rest").map_or("", |r| r.as_str());
+    let schedule = SHORTHANDS
+        .iter()
+        .find(|(short, _)| *short == word)
+        .or_else(|| {
+            ALIASES
+                .iter()
+                .find(|(alias, _)| alias.eq_ignore_ascii_case(&word))
+        });
+    match schedule {
+        // `tomorrow` alone starts the day, not 24 hours from now
+        Some(("tomorrow", _)) if rest.trim().is_empty() => {
+            String::from("in 1d at 0")
+        }
+        Some((_, schedule)) => format!("{}{}", schedule, rest),
+        None => spec.into_owned(),
+    }
+}
+
+/// Parses a time of day like `9`, `09:30` or `17:00:30`
+fn time_of_day(s: &str) -> Result<NaiveTime, ParseError> {
+    let midnight = NaiveDate::from_ymd_opt(2000, 1, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .unwrap();
+    Ok(AtHour::from_str(s)?.at(midnight)?.time())
+}
+
+impl FromStr for Every {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref KEYWORD: Regex =
+                Regex::new(r"(?i)\b(every|from|to|on|in|until|at)\b")
+                    .unwrap();
+            static ref INTERVAL: Regex =
+                Regex::new(r"^(?P<n>\d+)?\s*(?P<u>[a-zA-Z]+)$")
+                    .unwrap();
+        }
+
+        // The `to` of `second to last Sun` or `second-to-last Sun` is not
+        // a keyword
+        let keywords: Vec<_> = KEYWORD
+            .find_iter(s)
+            .filter(|k| {
+                !(k.as_str().eq_ignore_ascii_case("to")
+                    && (s[..k.start()].ends_with('-')
+                        || s[k.end()..].starts_with('-')
+                        || s[k.end()..]
+                            .trim_start()
+                            .to_lowercase()
+                            .starts_with("last")))
+            })
+            .collect();
+        if keywords.first().map(|k| s[..k.start()].trim().is_empty())
+            != Some(true)
+        {
+            Err(ParseError(format!(
+                "`{}` is not a valid schedule, e.g. use every 15m from \
+                09:00 to 17:00 on Mon..Fri",
+                s
+            )))?;
+        }
+
+        let mut clauses = std::collections::HashMap::new();
+        for (i, keyword) in keywords.iter().enumerate() {
+            let end =
+                keywords.get(i + 1).map_or(s.len(), |k| k.start());
+            let value = s[keyword.end()..end].trim();
+            let keyword = keyword.as_str().to_lowercase();
+            if value.is_empty() {
+                Err(ParseError(format!(
+                    "{} needs a value",
+                    keyword
+                )))?;
+            }
+            if clauses.insert(keyword.clone(), value).is_some() {
+                Err(ParseError(format!(
+                    "{} was given more than once",
+                    keyword
+                )))?;
+            }
+        }
+
+        let every = clauses.get("every").ok_or_else(|| {
+            ParseError(format!(
+                "`{}` does not say how often to run",
+                s
+            ))
+        })?;
+        let cap = INTERVAL.captures(every).ok_or_else(|| {
+            ParseError(format!(
+                "`{}` is not a valid interval, e.g. use 15m or day",
+                every
+            ))
+        })?;
+        let interval = match cap.name("n") {
+            Some(n) => u32::from_str(n.as_str()).map_err(|_| {
+                ParseError(format!("{} is too large", n.as_str()))
+            })?,
+            None => 1,
+        };
+
+        let window = match (clauses.get("from"), clauses.get("to")) {
+            (Some(from), Some(to)) => {
+                let (from, to) =
+                    (time_of_day(from)?, time_of_day(to)?);
+                if to < from {
+                    Err(ParseError(format!(
+                        "{} is before {}, ranges can't span midnight",
+                        to, from
+                    )))?;
+                }
+                Some((from, to))
+            }
+            (None, None) => None,
+            _ => Err(ParseError(String::from(
+                "from and to need to be given together",
+            )))?,
+        };
+
+        let unit = Unit::from_str(cap.name("u").unwrap().as_str())?;
+        let (days, day, nth) = match clauses.get("on") {
+            // A weekday like `2nd Tue` or a day like `15` or `last`
+            Some(on)
+                if unit == Unit::M
+                    && on.chars().any(char::is_alphabetic)
+                    && !on.eq_ignore_ascii_case("last") =>
+            {
+                match AtNthWeekDay::from_str(on)? {
+                    AtNthWeekDay(n, day, None) => {
+                        (vec![(day, None)], None, Some(n))
+                    }
+                    _ => Err(ParseError(format!(
+                        "`{}` has a time, use at to give it",
+                        on
+                    )))?,
+                }
+            }
+            Some(day) if unit == Unit::M => {
+                (Vec::new(), Some(month_day(day)?), None)
+            }
+            Some(days) => (
+                names::weekday_ranges(&names::weekday_set(days)?),
+                None,
+                None,
+            ),
+            None => (Vec::new(), None, None),
+        };
+
+        let until = clauses
+            .get("until")
+            .map(|until| {
+                NaiveDate::parse_from_str(until, "%Y-%m-%d").map_err(|_| {
+                    ParseError(format!(
+                        "`{}` is not a valid date, e.g. use 2026-12-31",
+                        until
+                    ))
+                })
+            })
+            .transpose()?;
+
+        if until.is_some() && unit > Unit::w {
+            Err(ParseError(String::from(
+                "until only applies to intervals up to a week",
+            )))?;
+        }
+        if !days.is_empty() && unit > Unit::w && nth.is_none() {
+            Err(ParseError(String::from(
+                "on only applies to intervals up to a week or monthly ones",
+            )))?;
+        }
+        let months = match clauses.get("in") {
+            Some(months) => IntRestriction::months(months)?,
+            None => IntRestriction::NoRestriction,
+        };
+        if clauses.contains_key("in")
+            && (unit == Unit::y || (unit == Unit::M && interval != 1))
+        {
+            Err(ParseError(String::from(
+                "in only applies to intervals up to a month",
+            )))?;
+        }
+        let at = clauses
+            .get("at")
+            .map(|at| time_of_day(at))
+            .transpose()?;
+        if at.is_some() && unit < Unit::d {
+            Err(ParseError(String::from(
+                "at only applies to intervals of a day or longer",
+            )))?;
+        }
+
+        Ok(Every {
+            interval,
+            unit,
+            at,
+            window,
+            days,
+            day,
+            nth,
+            months,
+            until,
+        })
+    }
+}
+
+/// A systemd calendar event like `Mon..Fri *-*-* 09:00:00`, as described
+/// in systemd.time(7)
+#[derive(Debug)]
+#[allow(non_snake_case)]
+pub struct Calendar {
+    wd: Vec<(Weekday, Option<Weekday>)>,
+    d: IntRestriction,
+    /// Whether `d` counts from the end of the month, written as `~`
+    last: bool,
+    M: IntRestriction,
+    y: IntRestriction,
+    s: IntRestriction,
+    m: IntRestriction,
+    h: IntRestriction,
+}
+
+#[derive(Debug)]
+pub enum IntRestriction {
+    Restrictions(Vec<IntRange>),
+    NoRestriction,
+}
+
+/// A value, `from..to` range or `from/repeat` repetition of a calendar
+/// component, ranges can repeat too
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct IntRange(i32, Option<i32>, Option<i32>);
+
+impl Display for IntRestriction {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        match self {
+            IntRestriction::NoRestriction => write!(f, "*"),
+            IntRestriction::Restrictions(ranges) => write!(
+                f,
+                "{}",
+                ranges
+                    .iter()
+                    .map(|IntRange(from, to, repeat)| {
+                        let mut range = format!("{:02}", from);
+                        if let Some(to) = to {
+                            range += &format!("..{:02}", to);
+                        }
+                        if let Some(repeat) = repeat {
+                            range += &format!("/{}", repeat);
+                        }
+                        range
+                    })
+                    .collect::<Vec<String>>()
+                    .join(",")
+            ),
+        }
+    }
+}
+
+impl IntRestriction {
+    /// Parses a component like `*`, `5`, `1..5`, `0/15` or `1,3..5`
+    fn parse(
+        s: &str,
+        name: &str,
+        min: i32,
+        max: i32,
+    ) -> Result<Self, ParseError> {
+        lazy_static! {
+            static ref RANGE: Regex = Regex::new(
+                r"^(?P<from>\d+)(\.\.(?P<to>\d+))?(/(?P<repeat>\d+))?$"
+            )
+            .unwrap();
+        }
+        if s == "*" {
+            return Ok(IntRestriction::NoRestriction);
+        }
+        let value =
+            |v: Option<regex::Match>| -> Result<_, ParseError> {
+                v.map(|v| {
+                    i32::from_str(v.as_str())
+                        .ok()
+                        .filter(|v| (min..=max).contains(v))
+                        .ok_or_else(|| {
+                            ParseError(format!(
+                                "{} is not a valid {}, use {} to {}",
+                                v.as_str(),
+                                name,
+                                min,
+                                max
+                            ))
+                        })
+                })
+                .transpose()
+            };
+        s.split(',')
+            .map(|range| {
+                let cap = RANGE.captures(range).ok_or_else(|| {
+                    ParseError(format!(
+                        "`{}` is not a valid {}, e.g. use 5, 1..5 or 0/15",
+                        range, name
+                    ))
+                })?;
+                let repeat = match cap.name("repeat") {
+                    Some(r) => Some(
+                        i32::from_str(r.as_str())
+                            .ok()
+                            .filter(|r| *r > 0)
+                            .ok_or_else(|| {
+                                ParseError(format!(
+                                    "{} is not a valid repetition",
+                                    r.as_str()
+                                ))
+                            })?,
+                    ),
+                    None => None,
+                };
+                Ok(IntRange(
+                    value(cap.name("from"))?.unwrap(),
+                    value(cap.name("to"))?,
+                    repeat,
+                ))
+            })
+            .collect::<Result<Vec<IntRange>, ParseError>>()
+            .map(|mut ranges| {
+                ranges.sort();
+                ranges.dedup();
+                IntRestriction::Restrictions(ranges)
+            })
+    }
+
+    /// Months like `Jan..Mar,Jun`, see `names::month_ranges`
+    fn months(list: &str) -> Result<Self, ParseError> {
+        Ok(IntRestriction::Restrictions(
+            names::month_ranges(list)?
+                .into_iter()
+                .map(|(from, to)| {
+                    IntRange(
+                        from as i32,
+                        (to != from).then_some(to as i32),
+                        None,
+                    )
+                })
+                .collect(),
+        ))
+    }
+
+    /// Expands two digit years like systemd, `70` to `99` are in the 20th
+    /// century, the others in the 21st
+    fn years(mut self) -> Result<Self, ParseError> {
+        if let IntRestriction::Restrictions(ranges) = &mut self {
+            for IntRange(from, to, _) in ranges.iter_mut() {
+                for year in std::iter::once(from).chain(to.iter_mut())
+                {
+                    *year = match *year {
+                        0..=69 => *year + 2000,
+                        70..=99 => *year + 1900,
+                        1970..=2199 => *year,
+                        _ => Err(ParseError(format!(
+                            "{} is not a valid year, use 1970 to 2199",
+                            year
+                        )))?,
+                    };
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// The single value this restricts to, if any
+    fn single(&self) -> Option<i32> {
+        match self {
+            IntRestriction::Restrictions(ranges) => {
+                match ranges.as_slice() {
+                    [IntRange(v, None, None)] => Some(*v),
+                    _ => None,
+                }
+            }
+            IntRestriction::NoRestriction => None,
+        }
+    }
+
+    fn matches(&self, value: i32) -> bool {
+        match self {
+            IntRestriction::NoRestriction => true,
+            IntRestriction::Restrictions(ranges) => {
+                ranges.iter().any(
+                    |IntRange(from, to, repeat)| match (to, repeat) {
+                        (None, None) => value == *from,
+                        (Some(to), None) => {
+                            (*from..=*to).contains(&value)
+                        }
+                        (to, Some(repeat)) => {
+                            value >= *from
+                                && (value - from) % repeat == 0
+                                && to.is_none_or(|to| value <= to)
+                        }
+                    },
+                )
+            }
+        }
+    }
+}
+
+impl Calendar {
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        let day = if self.last {
+            match month::days_in_month(date.year(), date.month()) {
+                Ok(days) => days - date.day() + 1,
+                Err(_) => return false,
+            }
+        } else {
+            date.day()
+        };
+        let weekday = date.weekday().num_days_from_monday();
+        self.y.matches(date.year())
+            && self.M.matches(date.month() as i32)
+            && self.d.matches(day as i32)
+            && (self.wd.is_empty()
+                || self.wd.iter().any(|(from, to)| {
+                    let to = to.unwrap_or(*from);
+                    (from.num_days_from_monday()
+                        ..=to.num_days_from_monday())
+                        .contains(&weekday)
+                }))
+    }
+
+    /// The first time of day matching the event, after `after` if given
+    fn first_time(
+        &self,
+        after: Option<NaiveTime>,
+    ) -> Option<NaiveTime> {
+        for h in (0..24).filter(|h| self.h.matches(*h)) {
+            for m in (0..60).filter(|m| self.m.matches(*m)) {
+                for s in (0..60).filter(|s| self.s.matches(*s)) {
+                    let time = NaiveTime::from_hms_opt(
+                        h as u32, m as u32, s as u32,
+                    )
+                    .unwrap();
+                    if after.is_none_or(|after| time > after) {
+                        return Some(time);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// The first time after `after` the event elapses, searched up to ten
+    /// years ahead
+    fn next_after(
+        &self,
+        after: NaiveDateTime,
+    ) -> Option<NaiveDateTime> {
+        let end = after.date() + Duration::days(3653);
+        let mut date = after.date();
+        while date <= end {
+            if self.matches_date(date) {
+                let after =
+                    (date == after.date()).then(|| after.time());
+                if let Some(time) = self.first_time(after) {
+                    return Some(date.and_time(time));
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+}
+
+/// What the shorthands of systemd.time(7) stand for
+const CALENDAR_SHORTHANDS: [(&str, &str); 9] = [
+    ("minutely", "*-*-* *:*:00"),
+    ("hourly", "*-*-* *:00:00"),
+    ("daily", "*-*-* 00:00:00"),
+    ("weekly", "Mon *-*-* 00:00:00"),
+    ("monthly", "*-*-01 00:00:00"),
+    ("yearly", "*-01-01 00:00:00"),
+    ("annually", "*-01-01 00:00:00"),
+    ("quarterly", "*-01,04,07,10-01 00:00:00"),
+    ("semiannually", "*-01,07-01 00:00:00"),
+];
+
+impl Display for Calendar {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        if !self.wd.is_empty() {
+            write!(
+                f,
+                "{} ",
+                self.wd
+                    .iter()
+                    .map(|(from, to)| match to {
+                        Some(to) => format!("{}..{}", from, to),
+                        None => from.to_string(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(",")
+            )?;
+        }
+        write!(
+            f,
+            "{}-{}{}{} {}:{}:{}",
+            self.y,
+            self.M,
+            if self.last { "~" } else { "-" },
+            self.d,
+            self.h,
+            self.m,
+            self.s
+        )
+    }
+}
+
+impl FromStr for Calendar {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let s = CALENDAR_SHORTHANDS
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(s))
+            .map_or(s, |(_, calendar)| calendar);
+        let mut tokens = s.split_whitespace().peekable();
+
+        let invalid = || {
+            ParseError(format!(
+                "`{}` is not a valid timer, e.g. use in 5d at 4, every day \
+                at 8 or a calendar event like Mon *-*-* 09:00",
+                s
+            ))
+        };
+
+        // A leading token naming months like `Jan..Mar-01` is a date
+        let is_date = |t: &str| {
+            t.contains(['-', '~'])
+                && names::month_ranges(
+                    t.split(['-', '~']).next().unwrap(),
+                )
+                .is_ok()
+        };
+        // Collected as set, so the days are sorted and merged like
+        // systemd does
+        let wd = match tokens.next_if(|t| {
+            t.starts_with(char::is_alphabetic) && !is_date(t)
+        }) {
+            Some(days) => names::weekday_ranges(
+                &names::weekday_set(days).map_err(|e| {
+                    if s.split_whitespace().count() == 1 {
+                        invalid()
+                    } else {
+                        e
+                    }
+                })?,
+            ),
+            None => Vec::new(),
+        };
+
+        let (mut date, mut time) = (None, None);
+        for token in tokens {
+            match token {
+                _ if token.contains(':') && time.is_none() => {
+                    time = Some(token)
+                }
+                _ if (token.contains('-') || token.contains('~'))
+                    && date.is_none()
+                    && time.is_none() =>
+                {
+                    date = Some(token)
+                }
+                _ => Err(ParseError(format!(
+                    "`{}` is not a valid calendar event, e.g. use \
+                    Mon..Fri *-*-* 09:00:00",
+                    s
+                )))?,
+            }
+        }
+        if wd.is_empty() && date.is_none() && time.is_none() {
+            Err(invalid())?;
+        }
+
+        let date = date.unwrap_or("*-*-*");
+        let last = date.contains('~');
+        let parts: Vec<&str> = date.split(['-', '~']).collect();
+        let (y, month, d) = match parts.as_slice() {
+            [y, month, d] => (*y, *month, *d),
+            [month, d] => ("*", *month, *d),
+            _ => Err(ParseError(format!(
+                "`{}` is not a valid date, use year-month-day",
+                date
+            )))?,
+        };
+
+        let time = time.unwrap_or("00:00:00");
+        let parts: Vec<&str> = time.split(':').collect();
+        let (h, m, sec) = match parts.as_slice() {
+            [h, m, sec] => (*h, *m, *sec),
+            [h, m] => (*h, *m, "00"),
+            _ => Err(ParseError(format!(
+                "`{}` is not a valid time, use hour:minute:second",
+                time
+            )))?,
+        };
+        if sec.contains('.') {
+            Err(ParseError(format!(
+                "`{}` has fractional seconds, which are not supported",
+                time
+            )))?;
+        }
+
+        Ok(Calendar {
+            wd,
+            y: IntRestriction::parse(y, "year", 0, 2199)?.years()?,
+            M: if month.contains(char::is_alphabetic) {
+                IntRestriction::months(month)?
+            } else {
+                IntRestriction::parse(month, "month", 1, 12)?
+            },
+            d: IntRestriction::parse(d, "day", 1, 31)?,
+            last,
+            h: IntRestriction::parse(h, "hour", 0, 23)?,
+            m: IntRestriction::parse(m, "minute", 0, 59)?,
+            s: IntRestriction::parse(sec, "second", 0, 59)?,
+        })
+    }
+}
+
+/// A systemd time span like `1h 30min`, numbers without unit are seconds
+#[allow(non_snake_case)]
+pub struct InDuration {
+    /// `infinity`, which never elapses
+    infinity: bool,
+    µs: Option<f64>,
     ms: Option<f64>,
     s: Option<f64>,
     m: Option<f64>,
@@ -634,152 +2225,795 @@ pub struct InDuration {
     y: Option<f64>,
 }
 
-impl Debug for InDuration {
-    fn fmt(
-        &self,
-        f: &mut Formatter<'_>,
-    ) -> Result<(), std::fmt::Error> {
-        write!(
-            f,
-            "In({})",
-            vec![
-                (self.y, "y"),
-                (self.M, "M"),
-                (self.w, "w"),
-                (self.d, "d"),
-                (self.h, "h"),
-                (self.m, "m"),
-                (self.s, "s"),
-                (self.ms, "ms"),
-                (self.µs, "µs"),
-            ]
-            .iter()
-            .filter_map(|(o, u)| o.map(|n| format!("{}{}", n, u)))
-            .collect::<Vec<String>>()
-            .join(" ")
+impl Debug for InDuration {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        if self.infinity {
+            return write!(f, "In(infinity)");
+        }
+        write!(
+            f,
+            "In({})",
+            [
+                (self.y, "y"),
+                (self.M, "M"),
+                (self.w, "w"),
+                (self.d, "d"),
+                (self.h, "h"),
+                (self.m, "m"),
+                (self.s, "s"),
+                (self.ms, "ms"),
+                (self.µs, "µs"),
+            ]
+            .iter()
+            .filter_map(|(o, u)| o.map(|n| format!("{}{}", n, u)))
+            .collect::<Vec<String>>()
+            .join(" ")
+        )
+    }
+}
+
+impl InDuration {
+    /// The length in microseconds, `None` for `infinity`
+    ///
+    /// Months and years are as long as systemd makes them, a twelfth of
+    /// 365.25 days and 365.25 days.
+    fn micros(&self) -> Option<i64> {
+        if self.infinity {
+            return None;
+        }
+        Some(
+            (((((((self.y.unwrap_or(0.0) * 12.0
+                + self.M.unwrap_or(0.0))
+                * 365.25
+                / 12.0
+                + self.w.unwrap_or(0.0) * 7.0
+                + self.d.unwrap_or(0.0))
+                * 24.0
+                + self.h.unwrap_or(0.0))
+                * 60.0
+                + self.m.unwrap_or(0.0))
+                * 60.0
+                + self.s.unwrap_or(0.0))
+                * 1000.0
+                + self.ms.unwrap_or(0.0))
+                * 1000.0
+                + self.µs.unwrap_or(0.0))
+            .round() as i64,
+        )
+    }
+
+    fn to_date(&self) -> Result<DateTime<chrono::Local>, ParseError> {
+        let micros = self.micros().ok_or_else(|| {
+            ParseError(String::from(
+                "in infinity never comes, use a finite time span",
+            ))
+        })?;
+        Ok(Local::now() + chrono::Duration::microseconds(micros))
+    }
+}
+
+/// Writes the units like systemd does, e.g. `1h 30min`
+impl Display for InDuration {
+    fn fmt(
+        &self,
+        f: &mut Formatter<'_>,
+    ) -> Result<(), std::fmt::Error> {
+        if self.infinity {
+            return write!(f, "infinity");
+        }
+        write!(
+            f,
+            "{}",
+            [
+                (self.y, "y"),
+                (self.M, "month"),
+                (self.w, "w"),
+                (self.d, "d"),
+                (self.h, "h"),
+                (self.m, "min"),
+                (self.s, "s"),
+                (self.ms, "ms"),
+                (self.µs, "us"),
+            ]
+            .iter()
+            .filter_map(|(o, u)| o.map(|n| format!("{}{}", n, u)))
+            .collect::<Vec<String>>()
+            .join(" ")
+        )
+    }
+}
+
+/// Parses systemd time spans like `2h`, `1y 12month`, `55s500ms` or
+/// `infinity`, numbers without unit are seconds
+impl FromStr for InDuration {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        lazy_static! {
+            static ref TIMESPAN: Regex =
+                Regex::new(r"^\s*(\d+(\.\d+)?\s*[a-zA-Zµ]*\s*)+$")
+                    .unwrap();
+            static ref UNITS: Regex =
+                Regex::new(r"(?P<n>\d+(\.\d+)?)\s*(?P<u>[a-zA-Zµ]*)")
+                    .unwrap();
+        }
+        let mut duration = InDuration {
+            infinity: false,
+            µs: None,
+            ms: None,
+            s: None,
+            m: None,
+            h: None,
+            d: None,
+            w: None,
+            M: None,
+            y: None,
+        };
+        if s.trim() == "infinity" {
+            duration.infinity = true;
+            return Ok(duration);
+        }
+        if !TIMESPAN.is_match(s) {
+            Err(ParseError(format!(
+                "`{}` is not a valid time span, e.g. use 1h 30min",
+                s
+            )))?;
+        }
+        for cap in UNITS.captures_iter(s) {
+            let n = f64::from_str(cap.name("n").unwrap().as_str())
+                .unwrap();
+            let u = cap.name("u").unwrap().as_str();
+            let field = match u {
+                "m" => &mut duration.m,
+                "M" => &mut duration.M,
+                _ if match_any!(u, "usec", "us", "µs") => {
+                    &mut duration.µs
+                }
+                _ if match_any!(u, "msec", "ms") => &mut duration.ms,
+                _ if u.is_empty()
+                    || match_any!(
+                        u, "seconds", "second", "sec", "s"
+                    ) =>
+                {
+                    &mut duration.s
+                }
+                _ if match_any!(u, "minutes", "minute", "min") => {
+                    &mut duration.m
+                }
+                _ if match_any!(u, "hours", "hour", "hr", "h") => {
+                    &mut duration.h
+                }
+                _ if match_any!(u, "days", "day", "d") => {
+                    &mut duration.d
+                }
+                _ if match_any!(u, "weeks", "week", "w") => {
+                    &mut duration.w
+                }
+                _ if match_any!(u, "months", "month") => {
+                    &mut duration.M
+                }
+                _ if match_any!(u, "years", "year", "y") => {
+                    &mut duration.y
+                }
+                _ => Err(ParseError(format!(
+                    "{} is not a valid Unit",
+                    u
+                )))?,
+            };
+            *field = Some(field.unwrap_or(0.0) + n);
+        }
+        Ok(duration)
+    }
+}
+
+#[derive(PartialEq, Debug)]
+pub enum DurationKind {
+    SinceTimer,
+    SinceBoot,
+    SinceLogin,
+    SinceSrvAct,
+    SinceSrvEnd,
+    IN,
+}
+
+impl TimerAble for Timer {
+    fn timer(&self) -> String {
+        self.timer_with(DstPolicy::default())
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+}
+
+impl Timer {
+    /// Like `timer()`, resolving local times affected by daylight saving
+    /// time with `dst`
+    pub fn timer_with(
+        &self,
+        dst: DstPolicy,
+    ) -> Result<String, ParseError> {
+        Ok(match self {
+            // Triggers given twice, e.g. `daily; every day`, are only
+            // written once
+            Timer::Timers(timers) => {
+                let mut lines: Vec<String> = Vec::new();
+                for timer in timers {
+                    for line in timer.timer_with(dst)?.lines() {
+                        if !lines.iter().any(|l| l == line) {
+                            lines.push(line.to_owned());
+                        }
+                    }
+                }
+                lines.join("\n")
+            }
+            Timer::Duration { kind, duration } => {
+                let duration_string = if *kind == DurationKind::IN {
+                    duration
+                        .to_date()?
+                        .format("%Y-%m-%d %H:%M:%S")
+                        .to_string()
+                } else {
+                    duration.to_string()
+                };
+                match kind {
+                    DurationKind::SinceTimer => {
+                        format!("OnActiveSec={}", duration_string)
+                    }
+                    DurationKind::SinceBoot => {
+                        format!("OnBootSec={}", duration_string)
+                    }
+                    DurationKind::SinceLogin => {
+                        format!("OnStartupSec={}", duration_string)
+                    }
+                    DurationKind::SinceSrvAct => {
+                        format!("OnUnitActiveSec={}", duration_string)
+                    }
+                    DurationKind::SinceSrvEnd => {
+                        format!(
+                            "OnUnitInactiveSec={}",
+                            duration_string
+                        )
+                    }
+                    DurationKind::IN => {
+                        format!("OnCalendar={}", duration_string)
+                    }
+                }
+            }
+            Timer::In(spec, None) => {
+                resolve(&Local, spec.date(Local::now())?, dst)?
+            }
+            Timer::In(spec, Some(zone)) => resolve(
+                zone,
+                spec.date(Utc::now().with_timezone(zone))?,
+                dst,
+            )?,
+            Timer::Every(every, None) => every
+                .timer(Local::now().naive_local().date(), None)?,
+            Timer::Every(every, Some(zone)) => every.timer(
+                Utc::now().with_timezone(zone).naive_local().date(),
+                Some(zone),
+            )?,
+            Timer::At(Absolute::Instant(instant), None) => {
+                instant.timer()
+            }
+            Timer::At(Absolute::Instant(instant), Some(zone)) => {
+                instant.with_timezone(zone).timer()
+            }
+            Timer::At(date, None) => {
+                resolve(&Local, date.date(Local::now())?, dst)?
+            }
+            Timer::At(date, Some(zone)) => resolve(
+                zone,
+                date.date(Utc::now().with_timezone(zone))?,
+                dst,
+            )?,
+            Timer::Calendar(calendar, zone) => format!(
+                "OnCalendar={}{}",
+                calendar,
+                zone.map(|z| format!(" {}", z.name()))
+                    .unwrap_or_default()
+            ),
+        })
+    }
+}
+
+impl Timer {
+    /// When a calendar event elapses next, in local time like
+    /// `systemd-analyze calendar` shows it, `None` for other timers or
+    /// events that never elapse
+    pub fn next_elapse(&self) -> Option<DateTime<Local>> {
+        match self {
+            Timer::Calendar(calendar, None) => {
+                let next = calendar
+                    .next_after(Local::now().naive_local())?;
+                Local.from_local_datetime(&next).earliest()
+            }
+            Timer::Calendar(calendar, Some(zone)) => {
+                let next = calendar.next_after(
+                    Utc::now().with_timezone(zone).naive_local(),
+                )?;
+                zone.from_local_datetime(&next)
+                    .earliest()
+                    .map(|next| next.with_timezone(&Local))
+            }
+            _ => None,
+        }
+    }
+
+    /// Warnings about triggers that can never elapse in this combination,
+    /// e.g. `SA 1h` without anything starting the service first
+    pub fn warnings(&self) -> Vec<String> {
+        let timers = match self {
+            Timer::Timers(timers) => timers.iter().collect(),
+            timer => vec![timer],
+        };
+        let mut warnings = Vec::new();
+        if timers.iter().all(|timer| {
+            matches!(
+                timer,
+                Timer::Duration {
+                    kind: DurationKind::SinceSrvAct
+                        | DurationKind::SinceSrvEnd,
+                    ..
+                }
+            )
+        }) {
+            warnings.push(String::from(
+                "OnUnitActiveSec= and OnUnitInactiveSec= count from the \
+                last start or stop of the service, without another trigger \
+                like `SB 5m` the timer never starts it",
+            ));
+        }
+        warnings
+    }
+
+    /// Fails for fixed points in time that already passed, as the timer
+    /// would never elapse
+    pub fn check_future(&self) -> Result<(), ParseError> {
+        let (date, now) = match self {
+            Timer::Timers(timers) => {
+                return timers
+                    .iter()
+                    .try_for_each(Timer::check_future)
+            }
+            Timer::At(date, None) => {
+                (date.date(Local::now())?, Local::now().naive_local())
+            }
+            Timer::At(date, Some(zone)) => {
+                let now = Utc::now().with_timezone(zone);
+                (date.date(now)?, now.naive_local())
+            }
+            Timer::Every(every, zone) => {
+                let today = match zone {
+                    Some(zone) => Utc::now()
+                        .with_timezone(zone)
+                        .naive_local()
+                        .date(),
+                    None => Local::now().naive_local().date(),
+                };
+                return every.check_future(today);
+            }
+            _ => return Ok(()),
+        };
+        if date <= now {
+            Err(ParseError(format!(
+                "{} is in the past, use --allow-past to create the timer \
+                anyway",
+                date.format("%Y-%m-%d %H:%M:%S")
+            )))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Timer {
+    /// Describes when the timer elapses as an English sentence, e.g.
+    /// `once, in 3 months on the last Friday at 17:00:00, that is on
+    /// Friday, January 29 2027 at 17:00`
+    pub fn explain(&self) -> String {
+        match self {
+            Timer::Timers(timers) => {
+                let mut sentences: Vec<String> = Vec::new();
+                for sentence in timers.iter().map(Timer::explain) {
+                    if !sentences.contains(&sentence) {
+                        sentences.push(sentence);
+                    }
+                }
+                sentences.join("; and ")
+            }
+            Timer::Calendar(calendar, zone) => {
+                format!("{}{}", calendar.explain(), in_zone(zone))
+            }
+            Timer::Duration { kind, duration } => match kind {
+                DurationKind::SinceTimer => {
+                    format!("{} after the timer last ran", duration)
+                }
+                DurationKind::SinceBoot => {
+                    format!("{} after boot", duration)
+                }
+                DurationKind::SinceLogin => format!(
+                    "{} after the service manager started",
+                    duration
+                ),
+                DurationKind::SinceSrvAct => format!(
+                    "{} after the service was last started",
+                    duration
+                ),
+                DurationKind::SinceSrvEnd => format!(
+                    "{} after the service last stopped",
+                    duration
+                ),
+                DurationKind::IN => {
+                    format!("once, in {}", duration)
+                }
+            },
+            Timer::In(spec, zone) => {
+                let date = match zone {
+                    Some(zone) => {
+                        spec.date(Utc::now().with_timezone(zone))
+                    }
+                    None => spec.date(Local::now()),
+                };
+                match date {
+                    Ok(date) => format!(
+                        "once, {}, that is {}{}",
+                        spec.explain(),
+                        long_date(date),
+                        in_zone(zone)
+                    ),
+                    Err(_) => format!(
+                        "once, {}{}",
+                        spec.explain(),
+                        in_zone(zone)
+                    ),
+                }
+            }
+            Timer::Every(every, zone) => {
+                format!("{}{}", every.explain(), in_zone(zone))
+            }
+            Timer::At(Absolute::Instant(instant), _) => {
+                format!(
+                    "once, {} UTC",
+                    long_date(instant.naive_utc())
+                )
+            }
+            Timer::At(date, zone) => {
+                let date = match zone {
+                    Some(zone) => {
+                        date.date(Utc::now().with_timezone(zone))
+                    }
+                    None => date.date(Local::now()),
+                };
+                match date {
+                    Ok(date) => {
+                        format!(
+                            "once, {}{}",
+                            long_date(date),
+                            in_zone(zone)
+                        )
+                    }
+                    Err(e) => format!("never, as {}", e),
+                }
+            }
+        }
+    }
+}
+
+/// Describes a part of a timer specification in English
+trait Explain {
+    fn explain(&self) -> String;
+}
+
+/// `on Friday, January 29 2027 at 17:00`
+fn long_date(date: NaiveDateTime) -> String {
+    date.format("on %A, %B %-d %Y at %H:%M").to_string()
+}
+
+fn in_zone(zone: &Option<Tz>) -> String {
+    zone.map(|zone| format!(" in {}", zone.name()))
+        .unwrap_or_default()
+}
+
+/// `1 day` or `3 days`
+fn count(n: impl Into<i64>, unit: &str) -> String {
+    match n.into() {
+        1 => format!("1 {}", unit),
+        n => format!("{} {}s", n, unit),
+    }
+}
+
+/// `1st`, `22nd`, `last` or `2nd to last`
+fn ordinal(n: i32) -> String {
+    match n {
+        -1 => String::from("last"),
+        _ if n < 0 => format!("{} to last", ordinal(-n)),
+        _ => format!(
+            "{}{}",
+            n,
+            match (n % 10, n % 100) {
+                (_, 11..=13) => "th",
+                (1, _) => "st",
+                (2, _) => "nd",
+                (3, _) => "rd",
+                _ => "th",
+            }
+        ),
+    }
+}
+
+/// ` at 17:00:00` if a time was given
+fn explain_at<A: Explain>(at: &Option<A>) -> String {
+    at.as_ref()
+        .map(|at| format!(" {}", at.explain()))
+        .unwrap_or_default()
+}
+
+impl Explain for AtSec {
+    fn explain(&self) -> String {
+        format!("at second {}", self.0)
+    }
+}
+
+impl Explain for AtMin {
+    fn explain(&self) -> String {
+        match &self.1 {
+            None => format!("at minute {}", self.0),
+            Some(AtSec(s)) => {
+                format!("at minute {} second {}", self.0, s)
+            }
+        }
+    }
+}
+
+impl Explain for AtHour {
+    fn explain(&self) -> String {
+        format!("at {}", self)
+    }
+}
+
+impl Explain for AtWeekDay {
+    fn explain(&self) -> String {
+        format!(
+            "on {}{}",
+            names::weekday_name(self.0),
+            explain_at(&self.1)
         )
     }
 }
 
-impl InDuration {
-    fn to_date(&self) -> DateTime<chrono::Local> {
-        Local::now()
-            + chrono::Duration::microseconds(
-                ((((((self.y.unwrap_or(0.0) * 365.25
-                    + self.M.unwrap_or(0.0) * 30.44
-                    + self.w.unwrap_or(0.0) * 7.0
-                    + self.d.unwrap_or(0.0))
-                    * 24.0
-                    + self.h.unwrap_or(0.0))
-                    * 60.0
-                    + self.m.unwrap_or(0.0))
-                    * 60.0
-                    + self.s.unwrap_or(0.0))
-                    * 1000.0
-                    + self.ms.unwrap_or(0.0))
-                    * 1000.0)
-                    .round() as i64,
-            )
+impl Explain for AtMonthDay {
+    fn explain(&self) -> String {
+        format!(
+            "on {} {}{}",
+            names::month_name(self.0),
+            self.1,
+            explain_at(&self.2)
+        )
     }
 }
 
-#[derive(PartialEq, Debug)]
-pub enum DurationKind {
-    SinceTimer,
-    SinceBoot,
-    SinceLogin,
-    SinceSrvAct,
-    SinceSrvEnd,
-    IN,
+impl Explain for WeekSub {
+    fn explain(&self) -> String {
+        match self {
+            WeekSub::AtHour(a) => a.explain(),
+            WeekSub::AtWeekDay(a) => a.explain(),
+        }
+    }
 }
 
-impl TimerAble for Timer {
-    fn timer(&self) -> String {
+impl Explain for MonthSub {
+    fn explain(&self) -> String {
         match self {
-            Timer::Timers(timers) => timers
-                .iter()
-                .map(|t| t.timer())
-                .collect::<Vec<String>>()
-                .join("\n"),
-            Timer::Duration { kind, duration } => {
-                let duration_string = if *kind == DurationKind::IN {
-                    duration
-                        .to_date()
-                        .format("%Y-%m-%d %H:%M:%S")
-                        .to_string()
-                } else {
+            MonthSub::AtDay(AtDay(-1, at)) => {
+                format!("on the last day{}", explain_at(at))
+            }
+            MonthSub::AtDay(AtDay(d, at)) => {
+                format!(
+                    "on the {} day{}",
+                    ordinal(*d),
+                    explain_at(at)
+                )
+            }
+            MonthSub::AtNthWeekDay(AtNthWeekDay(n, d, at)) => {
+                format!(
+                    "on the {} {}{}",
+                    ordinal((*n).into()),
+                    names::weekday_name(*d),
+                    explain_at(at)
+                )
+            }
+            MonthSub::AtHour(a) => a.explain(),
+        }
+    }
+}
+
+impl Explain for In {
+    fn explain(&self) -> String {
+        let (n, unit, at) = match self {
+            In::Sec(InSec(n)) => (*n, "second", String::new()),
+            In::Min(InMin(n, at)) => (*n, "minute", explain_at(at)),
+            In::Hour(InHour(n, at)) => (*n, "hour", explain_at(at)),
+            In::Day(InDay(n, at)) => (*n, "day", explain_at(at)),
+            In::Week(InWeek(n, at)) => (*n, "week", explain_at(at)),
+            In::Month(InMonth(n, at)) => {
+                ((*n).into(), "month", explain_at(at))
+            }
+            In::Year(InYear(n, at)) => {
+                ((*n).into(), "year", explain_at(at))
+            }
+            In::Next(AtWeekDay(day, at)) => {
+                return format!(
+                    "next {}{}",
+                    names::weekday_name(*day),
+                    explain_at(at)
+                )
+            }
+        };
+        format!("in {}{}", count(n, unit), at)
+    }
+}
+
+impl Explain for Calendar {
+    fn explain(&self) -> String {
+        let days = self
+            .wd
+            .iter()
+            .map(|(from, to)| match to {
+                Some(to) => {
                     format!(
-                        "{}y {}M {}w {}d {}h {}m {}s {}ms {}µs",
-                        duration.y.unwrap_or(0.0),
-                        duration.M.unwrap_or(0.0),
-                        duration.w.unwrap_or(0.0),
-                        duration.d.unwrap_or(0.0),
-                        duration.h.unwrap_or(0.0),
-                        duration.m.unwrap_or(0.0),
-                        duration.s.unwrap_or(0.0),
-                        duration.ms.unwrap_or(0.0),
-                        duration.µs.unwrap_or(0.0),
+                        "{} to {}",
+                        names::weekday_name(*from),
+                        names::weekday_name(*to)
                     )
-                };
-                match kind {
-                    DurationKind::SinceTimer => {
-                        format!("OnActiveSec={}", duration_string)
-                    }
-                    DurationKind::SinceBoot => {
-                        format!("OnBootSec={}", duration_string)
-                    }
-                    DurationKind::SinceLogin => {
-                        format!("OnStartupSec={}", duration_string)
-                    }
-                    DurationKind::SinceSrvAct => {
-                        format!("OnUnitActiveSec={}", duration_string)
+                }
+                None => names::weekday_name(*from).to_owned(),
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        let date =
+            match (&self.y, &self.M, self.d.single(), self.last) {
+                (
+                    IntRestriction::NoRestriction,
+                    IntRestriction::NoRestriction,
+                    _,
+                    false,
+                ) if matches!(
+                    self.d,
+                    IntRestriction::NoRestriction
+                ) =>
+                {
+                    if days.is_empty() {
+                        String::from("every day")
+                    } else {
+                        format!("every {}", days)
                     }
-                    DurationKind::SinceSrvEnd => {
-                        format!(
-                            "OnUnitInactiveSec={}",
-                            duration_string
-                        )
+                }
+                (
+                    IntRestriction::NoRestriction,
+                    IntRestriction::NoRestriction,
+                    Some(d),
+                    last,
+                ) => format!(
+                    "on the {} day of every month{}",
+                    ordinal(if last { -d } else { d }),
+                    if days.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" if it is a {}", days)
                     }
-                    DurationKind::IN => {
-                        format!("OnCalendar={}", duration_string)
+                ),
+                _ => format!(
+                    "on dates matching {}-{}{}{}{}",
+                    self.y,
+                    self.M,
+                    if self.last { "~" } else { "-" },
+                    self.d,
+                    if days.is_empty() {
+                        String::new()
+                    } else {
+                        format!(" if it is a {}", days)
                     }
-                }
+                ),
+            };
+        match (self.h.single(), self.m.single(), self.s.single()) {
+            (Some(h), Some(m), Some(s)) => {
+                format!("{} at {:02}:{:02}:{:02}", date, h, m, s)
             }
-            Timer::In(spec) => match spec {
-                In::Sec(t) => t.timer(),
-                In::Min(t) => t.timer(),
-                In::Hour(t) => t.timer(),
-                In::Day(t) => t.timer(),
-                In::Week(t) => t.timer(),
-                In::Month(t) => t.timer(),
-            },
-            _ => todo!("timer for all Timers"),
-        }
-    }
-}
-
-fn get_days_from_month<T: TimeZone>(date: DateTime<T>) -> u32 {
-    NaiveDate::from_ymd(
-        match date.month() {
-            12 => date.year() + 1,
-            _ => date.year(),
-        },
-        match date.month() {
-            12 => 1,
-            _ => date.month() + 1,
-        },
-        1,
-    )
-    .signed_duration_since(NaiveDate::from_ymd(
-        date.year(),
-        date.month(),
-        1,
-    ))
-    .num_days() as u32
+            _ => format!(
+                "{} at times matching {}:{}:{}",
+                date, self.h, self.m, self.s
+            ),
+        }
+    }
+}
+
+impl Explain for Every {
+    fn explain(&self) -> String {
+        let unit = match self.unit {
+            Unit::s => "second",
+            Unit::m => "minute",
+            Unit::h => "hour",
+            Unit::d => "day",
+            Unit::w => "week",
+            Unit::M => "month",
+            Unit::y => "year",
+        };
+        let mut sentence = match self.interval {
+            1 => format!("every {}", unit),
+            n => format!("every {}", count(n, unit)),
+        };
+        if let Some((from, to)) = self.window {
+            sentence += &format!(
+                " from {} to {}",
+                from.format("%H:%M"),
+                to.format("%H:%M")
+            );
+        }
+        if let (Some(n), [(day, None)]) =
+            (self.nth, self.days.as_slice())
+        {
+            sentence += &format!(
+                " on the {} {}",
+                ordinal(n.into()),
+                names::weekday_name(*day)
+            );
+        } else if !self.days.is_empty() {
+            sentence += &format!(
+                " on {}",
+                self.days
+                    .iter()
+                    .map(|(from, to)| match to {
+                        Some(to) => format!(
+                            "{} to {}",
+                            names::weekday_name(*from),
+                            names::weekday_name(*to)
+                        ),
+                        None => names::weekday_name(*from).to_owned(),
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            );
+        }
+        match self.day {
+            Some(-1) => sentence += " on the last day",
+            Some(d) => {
+                sentence += &format!(" on the {} day", ordinal(d))
+            }
+            None => {}
+        }
+        if let IntRestriction::Restrictions(ranges) = &self.months {
+            sentence += &format!(
+                    " in {}",
+                    ranges
+                        .iter()
+                        .map(|IntRange(from, to, _)| match to {
+                            Some(to) => format!(
+                                "{} to {}",
+                                names::month_name(*from as u32),
+                                names::month_name(*to as u32)
+                            ),
+                            None => names::month_name(*from as u32)
+                                .to_owned(),
+                        })
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+        }
+        if let Some(at) = self.at {
+            sentence += &format!(" at {}", at.format("%H:%M"));
+        }
+        if let Some(until) = self.until {
+            sentence +=
+                &format!(" until {}", until.format("%B %-d %Y"));
+        }
+        sentence
+    }
 }
 
 #[derive(Debug, Clone)]
-pub struct ParseError(String);
+pub struct ParseError(pub String);
 
 impl Display for ParseError {
     fn fmt(
@@ -794,17 +3028,168 @@ impl FromStr for Timer {
     type Err = ParseError;
     fn from_str(input: &str) -> Result<Self, <Self as FromStr>::Err> {
         if input.contains(';') {
-            input
-                    .split(';')
-                    .map(|i| Timer::from_str(i))
-                    .collect::<Result<Vec<Timer>, <Self as FromStr>::Err>>().map(|v| Timer::Timers(v))
+            let timers = input
+                .split(';')
+                .filter(|i| !i.trim().is_empty())
+                .map(Timer::from_str)
+                .collect::<Result<Vec<Timer>, <Self as FromStr>::Err>>()?;
+            if timers.is_empty() {
+                Err(ParseError(format!(
+                    "`{}` has no triggers between the `;`, e.g. use \
+                    daily; SB 5m",
+                    input.trim()
+                )))?;
+            }
+            Ok(Timer::Timers(timers))
         } else {
-            In::from_str(input).map(|i| Timer::In(i))
+            lazy_static! {
+                static ref DIRECTIVE: Regex = Regex::new(
+                    r"^\s*(?P<key>On[A-Za-z]+)\s*=\s*(?P<value>.*?)\s*$"
+                )
+                .unwrap();
+                static ref EVERY: Regex =
+                    Regex::new(r"(?i)^\s*(every|from)\b").unwrap();
+                static ref ABSOLUTE: Regex =
+                    Regex::new(r"(?i)^\s*(@|on\b|(at\s+)?\d{4}-)")
+                        .unwrap();
+                static ref IN: Regex =
+                    Regex::new(r"(?i)^\s*(in?|next)\b").unwrap();
+                // The fields of the README, e.g. `SB 5m` for
+                // `OnBootSec=5min`, `ev 1h` repeats after each start
+                static ref FIELD: Regex = Regex::new(concat!(
+                    r"^\s*(?P<field>(?i:since(timer|boot|login|srvact|srvend))",
+                    r"|ST|SB|SL|SA|SE|(?i:ev))\s+(?P<span>.*?)\s*$"
+                ))
+                .unwrap();
+                static ref CALENDAR: Regex =
+                    Regex::new(r"^\s*(C|(?i:calendar))\s+(?P<spec>.*)$")
+                        .unwrap();
+            }
+            // Lines of an existing [Timer] section, e.g. `OnBootSec=5min`
+            if let Some(cap) = DIRECTIVE.captures(input) {
+                let value = cap.name("value").unwrap().as_str();
+                let kind = match cap.name("key").unwrap().as_str() {
+                    "OnCalendar" => {
+                        let (spec, zone) = split_zone(value)?;
+                        return Ok(Timer::Calendar(
+                            Calendar::from_str(&spec)?,
+                            zone,
+                        ));
+                    }
+                    "OnActiveSec" => DurationKind::SinceTimer,
+                    "OnBootSec" => DurationKind::SinceBoot,
+                    "OnStartupSec" => DurationKind::SinceLogin,
+                    "OnUnitActiveSec" => DurationKind::SinceSrvAct,
+                    "OnUnitInactiveSec" => DurationKind::SinceSrvEnd,
+                    key => Err(ParseError(format!(
+                        "{} is not a timer directive",
+                        key
+                    )))?,
+                };
+                return Ok(Timer::Duration {
+                    kind,
+                    duration: InDuration::from_str(value)?,
+                });
+            }
+            if let Some(cap) = FIELD.captures(input) {
+                let field = cap.name("field").unwrap().as_str();
+                let kind = match field.to_lowercase().as_str() {
+                    "sincetimer" | "st" => DurationKind::SinceTimer,
+                    "sinceboot" | "sb" => DurationKind::SinceBoot,
+                    "sincelogin" | "sl" => DurationKind::SinceLogin,
+                    "sincesrvact" | "sa" | "ev" => {
+                        DurationKind::SinceSrvAct
+                    }
+                    _ => DurationKind::SinceSrvEnd,
+                };
+                return Ok(Timer::Duration {
+                    kind,
+                    duration: InDuration::from_str(
+                        cap.name("span").unwrap().as_str(),
+                    )?,
+                });
+            }
+            if let Some(cap) = CALENDAR.captures(input) {
+                let (spec, zone) =
+                    split_zone(cap.name("spec").unwrap().as_str())?;
+                return Ok(Timer::Calendar(
+                    Calendar::from_str(&spec)?,
+                    zone,
+                ));
+            }
+            // The zone goes first, so `at 9 UTC` is seen as a bare time
+            let (spec, zone) = split_zone(input)?;
+            let spec = expand(&spec);
+            let spec = spec.as_str();
+            if EVERY.is_match(spec) {
+                Ok(Timer::Every(Every::from_str(spec)?, zone))
+            } else if ABSOLUTE.is_match(spec) {
+                // Calendar events like `2026-*-01` start the same way
+                Absolute::from_str(spec)
+                    .map(|date| Timer::At(date, zone))
+                    .or_else(|e| {
+                        Calendar::from_str(spec)
+                            .map(|c| Timer::Calendar(c, zone))
+                            .map_err(|_| e)
+                    })
+            } else if IN.is_match(spec) {
+                In::from_str(spec).map(|i| Timer::In(i, zone))
+            } else {
+                Calendar::from_str(spec)
+                    .map(|c| Timer::Calendar(c, zone))
+            }
         }
     }
 }
 
-#[derive(PartialEq, PartialOrd)]
+/// Splits a trailing time zone like `Europe/Berlin` or `UTC` off a spec,
+/// the zone may also come before an `until` date
+fn split_zone(
+    input: &str,
+) -> Result<(Cow<'_, str>, Option<Tz>), ParseError> {
+    lazy_static! {
+        static ref ZONE: Regex = Regex::new(concat!(
+            r"^(?P<spec>.*?)\s+",
+            r"(?P<tz>(?i:utc|gmt)|[A-Za-z]+(/[\w+-]+)+)",
+            r"(?P<until>\s+(?i:until)\s+\S+)?\s*$"
+        ))
+        .unwrap();
+    }
+    Ok(match ZONE.captures(input) {
+        Some(cap) => (
+            match cap.name("until") {
+                Some(until) => Cow::Owned(format!(
+                    "{}{}",
+                    &cap["spec"],
+                    until.as_str()
+                )),
+                None => {
+                    Cow::Borrowed(cap.name("spec").unwrap().as_str())
+                }
+            },
+            Some(parse_zone(&cap["tz"])?),
+        ),
+        None => (Cow::Borrowed(input), None),
+    })
+}
+
+/// Looks up a zone in the bundled tz database, `utc` and `gmt` are
+/// accepted in any case
+fn parse_zone(tz: &str) -> Result<Tz, ParseError> {
+    let name = match tz {
+        _ if tz.eq_ignore_ascii_case("utc") => "UTC",
+        _ if tz.eq_ignore_ascii_case("gmt") => "GMT",
+        _ => tz,
+    };
+    Tz::from_str(name).map_err(|_| {
+        ParseError(format!(
+            "`{}` is not a known time zone, e.g. use Europe/Berlin or UTC",
+            tz
+        ))
+    })
+}
+
+#[derive(PartialEq, PartialOrd, Debug)]
 #[allow(non_camel_case_types)]
 enum Unit {
     s = 0,
@@ -816,7 +3201,7 @@ enum Unit {
     y = 6,
 }
 impl Unit {
-    fn to_lower_or_equal(&self, other: &Self, v: u32) -> Option<u32> {
+    fn to_lower_or_equal(&self, other: &Self, v: u64) -> Option<u64> {
         if self == other {
             Some(v)
         } else {
@@ -861,15 +3246,6 @@ impl Display for Unit {
     }
 }
 
-macro_rules! match_any {
-    ($value:expr, $first:expr) => {
-        $value.eq_ignore_ascii_case($first)
-    };
-    ($value:expr, $first:expr, $($pattern:expr), +) => {
-        $value.eq_ignore_ascii_case($first) || match_any!($value, $($pattern), +)
-    };
-}
-
 impl FromStr for Unit {
     type Err = ParseError;
 
@@ -904,14 +3280,37 @@ impl FromStr for In {
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut lowest_unit = None;
-        let mut total = 0u32;
+        // Summed in u64 so large spans are rejected below, not overflowing
+        let mut total = 0u64;
 
         lazy_static! {
+            static ref NEXT: Regex = Regex::new(
+                r"(?i)^\s*next\s+(?P<d>[A-Za-z]+)(\s+at\b(?P<at>.*))?$"
+            )
+            .unwrap();
             static ref IN_AT: Regex =
-                Regex::new(r"^in?\b(?P<in>.*?)(\bat?\b(?P<at>.*))?$")
+                Regex::new(r"(?i)^in?\b(?P<in>.*?)(\bat?\b(?P<at>.*))?$")
                     .unwrap();
             static ref UNITS: Regex =
-                Regex::new(r"(?P<n>\d+)\s*(?P<u>[a-zA-z])").unwrap();
+                Regex::new(r"(?P<n>\d+)\s*(?P<u>[a-zA-Z]+)").unwrap();
+            static ref ONLY_UNITS: Regex =
+                Regex::new(r"^\s*(\d+\s*[a-zA-Z]+\s*)*$").unwrap();
+        }
+
+        if let Some(cap) = NEXT.captures(s) {
+            let day = cap.name("d").unwrap().as_str();
+            return Ok(In::Next(AtWeekDay(
+                names::weekday(day).ok_or_else(|| {
+                    ParseError(format!(
+                        "`{}` is not a valid Weekday",
+                        day
+                    ))
+                })?,
+                match cap.name("at") {
+                    Some(at) => Some(AtHour::from_str(at.as_str())?),
+                    None => None,
+                },
+            )));
         }
 
         let caps = IN_AT.captures(s).ok_or(ParseError(format!(
@@ -919,44 +3318,43 @@ impl FromStr for In {
             s
         )))?;
 
+        if !ONLY_UNITS.is_match(caps.name("in").unwrap().as_str()) {
+            Err(ParseError(format!(
+                "`{}` is not a valid time, e.g. use 5d or 1h 30m",
+                caps.name("in").unwrap().as_str().trim()
+            )))?;
+        }
+
         for cap in
             UNITS.captures_iter(caps.name("in").unwrap().as_str())
         {
-            let current =
-                u32::from_str(cap.name("n").unwrap().as_str())
-                    .unwrap();
+            let current = u64::from(parse_number(
+                cap.name("n").unwrap().as_str(),
+            )?);
             let unit =
                 Unit::from_str(cap.name("u").unwrap().as_str())?;
-            if lowest_unit.is_some()
-                && unit >= *lowest_unit.as_ref().unwrap()
-            {
-                total += unit
-                    .to_lower_or_equal(
-                        &lowest_unit.as_ref().unwrap(),
-                        current,
-                    )
-                    .ok_or(ParseError(format!(
+            match &lowest_unit {
+                Some(lowest) if unit >= *lowest => {
+                    total += unit
+                        .to_lower_or_equal(lowest, current)
+                        .ok_or(ParseError(format!(
                         "The units {} and {} are not compatible",
-                        &unit,
-                        &lowest_unit.as_ref().unwrap()
+                        &unit, lowest
                     )))?
-            } else {
-                total = current
-                    + if lowest_unit.is_some() {
-                        lowest_unit
-                                    .as_ref()
-                                    .unwrap()
-                                    .to_lower_or_equal(
-                                        &unit, total,
-                                    )
-                                    .ok_or(ParseError(format!(
-                                                "The units {} and {} are not compatible",
-                                                &unit,
-                                                &lowest_unit.as_ref().unwrap())))?
-                    } else {
-                        0
-                    };
-                lowest_unit = Some(unit);
+                }
+                _ => {
+                    total = current
+                        + match &lowest_unit {
+                            Some(lowest) => lowest
+                                .to_lower_or_equal(&unit, total)
+                                .ok_or(ParseError(format!(
+                                    "The units {} and {} are not compatible",
+                                    &unit, lowest
+                                )))?,
+                            None => 0,
+                        };
+                    lowest_unit = Some(unit);
+                }
             }
         }
 
@@ -968,6 +3366,13 @@ impl FromStr for In {
             )))?;
         }
 
+        let total = u32::try_from(total).map_err(|_| {
+            ParseError(format!(
+                "`{}` is too far in the future",
+                caps.name("in").unwrap().as_str().trim()
+            ))
+        })?;
+
         let ats = if caps.name("at").is_some() {
             Some(caps.name("at").unwrap().as_str().trim())
         } else {
@@ -1007,15 +3412,570 @@ impl FromStr for In {
                 total.into(),
                 Some(WeekSub::from_str(a)?),
             )),
-            (Some(Unit::M), None) => {
-                In::Month(InMonth(total.into(), None))
-            }
+            (Some(Unit::M), None) => In::Month(InMonth(total, None)),
             (Some(Unit::M), Some(a)) => In::Month(InMonth(
-                total.into(),
+                total,
                 Some(MonthSub::from_str(a)?),
             )),
-
-            _ => todo!(),
+            (Some(Unit::y), None) => In::Year(InYear(total, None)),
+            (Some(Unit::y), Some(a)) => In::Year(InYear(
+                total,
+                Some(AtMonthDay::from_str(a)?),
+            )),
+            (None, _) => unreachable!("no time was rejected above"),
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The `[Timer]` lines autod writes for `spec`
+    fn timer(spec: &str) -> String {
+        Timer::from_str(spec)
+            .and_then(|timer| timer.timer_with(DstPolicy::Earliest))
+            .unwrap_or_else(|e| panic!("{}: {}", spec, e.0))
+    }
+
+    #[test]
+    fn instants_are_written_in_utc() {
+        for (spec, timer_line) in [
+            ("2026-11-03T14:00+01:00", "2026-11-03 13:00:00 UTC"),
+            ("2026-11-03T14:00-05:30", "2026-11-03 19:30:00 UTC"),
+            ("2026-11-03T14:00Z", "2026-11-03 14:00:00 UTC"),
+            ("@1793714400", "2026-11-03 14:00:00 UTC"),
+            (
+                "@1793714400 Europe/Berlin",
+                "2026-11-03 15:00:00 Europe/Berlin",
+            ),
+        ] {
+            assert_eq!(
+                timer(spec),
+                format!("OnCalendar={}", timer_line),
+                "{}",
+                spec
+            );
+        }
+    }
+
+    #[test]
+    fn explanations() {
+        let explain = |spec| Timer::from_str(spec).unwrap().explain();
+        for (spec, explanation) in [
+            (
+                "every 15m from 09:00 to 17:00 on Mon..Fri",
+                "every 15 minutes from 09:00 to 17:00 on Monday to Friday",
+            ),
+            ("every month on 2nd Tue at 9", "every month on the 2nd Tuesday at 09:00"),
+            ("every day at 9 Europe/Berlin", "every day at 09:00 in Europe/Berlin"),
+            ("every day until 2027-12-31", "every day until December 31 2027"),
+            ("Mon..Fri *-*-* 09:00", "every Monday to Friday at 09:00:00"),
+            (
+                "Sat *-*-1..7 18:00",
+                "on dates matching *-*-01..07 if it is a Saturday at 18:00:00",
+            ),
+            ("SinceBoot 5min", "5min after boot"),
+            ("@1793714400", "once, on Tuesday, November 3 2026 at 14:00 UTC"),
+            ("hourly; OnBootSec=10m", "every hour; and 10min after boot"),
+        ] {
+            assert_eq!(explain(spec), explanation, "{}", spec);
+        }
+        assert!(explain("in 2d at 9")
+            .starts_with("once, in 2 days at 09:00:00, that is on "));
+    }
+
+    #[test]
+    fn mixed_triggers() {
+        assert_eq!(
+            timer(
+                "daily; SinceBoot 5m; Mon..Fri *-*-* 09:00; \
+                every 15m from 9 to 10 on Sat; SA 1h"
+            ),
+            "OnCalendar=*-*-* 00:00:00\n\
+            OnBootSec=5min\n\
+            OnCalendar=Mon..Fri *-*-* 09:00:00\n\
+            OnCalendar=Sat *-*-* 09:00/15:00\n\
+            OnCalendar=Sat *-*-* 10:00:00\n\
+            OnUnitActiveSec=1h"
+        );
+        assert_eq!(
+            timer("daily; every day; dly; SB 5m; OnBootSec=5min;"),
+            "OnCalendar=*-*-* 00:00:00\nOnBootSec=5min"
+        );
+        assert!(Timer::from_str("SA 1h; SB 5m")
+            .unwrap()
+            .warnings()
+            .is_empty());
+        assert_eq!(
+            Timer::from_str("SA 1h; SE 2h").unwrap().warnings().len(),
+            1
+        );
+        for spec in [";", "; ;", " ;; "] {
+            assert!(Timer::from_str(spec)
+                .unwrap_err()
+                .0
+                .contains("no triggers"));
+        }
+    }
+
+    #[test]
+    fn zone_after_a_bare_time() {
+        assert_eq!(
+            timer("at 09:00 Europe/Berlin"),
+            "OnCalendar=*-*-* 09:00:00 Europe/Berlin"
+        );
+        assert_eq!(
+            timer("at 9 UTC"),
+            "OnCalendar=*-*-* 09:00:00 UTC"
+        );
+        assert_eq!(
+            timer("daily at 8 Europe/Berlin"),
+            "OnCalendar=*-*-* 08:00:00 Europe/Berlin"
+        );
+        assert_eq!(
+            timer("noon Europe/Paris"),
+            "OnCalendar=*-*-* 12:00:00 Europe/Paris"
+        );
+    }
+
+    fn berlin(
+        spec: &str,
+        dst: DstPolicy,
+    ) -> Result<String, ParseError> {
+        let tz = parse_zone("Europe/Berlin").unwrap();
+        let local =
+            NaiveDateTime::parse_from_str(spec, "%Y-%m-%d %H:%M")
+                .unwrap();
+        resolve(&tz, local, dst)
+    }
+
+    #[test]
+    fn spring_forward() {
+        // 02:00 to 03:00 is skipped
+        assert_eq!(
+            berlin("2027-03-28 02:30", DstPolicy::Earliest).unwrap(),
+            "OnCalendar=2027-03-28 01:30:00 Europe/Berlin"
+        );
+        assert_eq!(
+            berlin("2027-03-28 02:30", DstPolicy::Latest).unwrap(),
+            "OnCalendar=2027-03-28 03:30:00 Europe/Berlin"
+        );
+        assert!(berlin("2027-03-28 02:30", DstPolicy::Error).is_err());
+        assert_eq!(
+            berlin("2027-03-28 03:30", DstPolicy::Error).unwrap(),
+            "OnCalendar=2027-03-28 03:30:00 Europe/Berlin"
+        );
+    }
+
+    #[test]
+    fn fall_back() {
+        // 02:00 to 03:00 happens twice, first in summer time
+        assert_eq!(
+            berlin("2026-10-25 02:30", DstPolicy::Earliest).unwrap(),
+            "OnCalendar=2026-10-25 00:30:00 UTC"
+        );
+        assert_eq!(
+            berlin("2026-10-25 02:30", DstPolicy::Latest).unwrap(),
+            "OnCalendar=2026-10-25 01:30:00 UTC"
+        );
+        assert!(berlin("2026-10-25 02:30", DstPolicy::Error).is_err());
+        assert_eq!(
+            berlin("2026-10-25 01:30", DstPolicy::Error).unwrap(),
+            "OnCalendar=2026-10-25 01:30:00 Europe/Berlin"
+        );
+    }
+
+    #[test]
+    fn dst_policy_of_fixed_dates() {
+        let at = |spec: &str, dst| {
+            Timer::from_str(spec).unwrap().timer_with(dst)
+        };
+        assert_eq!(
+            at(
+                "at 2027-03-28 02:30 Europe/Berlin",
+                DstPolicy::Latest
+            )
+            .unwrap(),
+            "OnCalendar=2027-03-28 03:30:00 Europe/Berlin"
+        );
+        assert!(at(
+            "at 2026-10-25 02:30 Europe/Berlin",
+            DstPolicy::Error
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn feb_29_in_years_without_one() {
+        let in_years = |years, at: &str| {
+            let now = Utc.from_utc_datetime(
+                &NaiveDate::from_ymd_opt(2026, 10, 19)
+                    .unwrap()
+                    .and_hms_opt(12, 0, 0)
+                    .unwrap(),
+            );
+            InYear(years, Some(AtMonthDay::from_str(at).unwrap()))
+                .date(now)
+                .unwrap()
+                .to_string()
+        };
+        assert_eq!(in_years(1, "Feb 29"), "2027-02-28 00:00:00");
+        assert_eq!(in_years(1, "02-29 10:00"), "2027-02-28 10:00:00");
+        assert_eq!(in_years(2, "Feb 29"), "2028-02-29 00:00:00");
+        assert_eq!(in_years(1, "Mar 1"), "2027-03-01 00:00:00");
+        assert!(timer("in 1y at Feb 29").starts_with("OnCalendar="));
+    }
+
+    #[test]
+    fn numbers_too_large() {
+        let error = |spec| match Timer::from_str(spec) {
+            Ok(_) => panic!("`{}` was accepted", spec),
+            Err(e) => e.0,
+        };
+        assert!(error("i 99999999999d").contains("too large"));
+        assert!(error("in 1d 99999999999h").contains("too large"));
+        assert!(error("every 99999999999m").contains("too large"));
+        assert!(error("i 4000000000w 3s").contains("too far"));
+
+        let now = Utc.from_utc_datetime(
+            &NaiveDate::from_ymd_opt(2026, 10, 19)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+        );
+        for spec in
+            ["i 4000000000d", "i 4000000000h", "i 4000000000y"]
+        {
+            assert!(
+                In::from_str(spec).unwrap().date(now).is_err(),
+                "{}",
+                spec
+            );
+        }
+        assert_eq!(
+            In::from_str("i 4000000000s").unwrap().date(now).unwrap(),
+            NaiveDate::from_ymd_opt(2153, 7, 21)
+                .unwrap()
+                .and_hms_opt(19, 6, 40)
+                .unwrap()
+        );
+    }
+
+    /// The `OnCalendar=` lines of an `every` schedule created on `today`
+    fn every(
+        spec: &str,
+        today: (i32, u32, u32),
+    ) -> Result<String, String> {
+        let today =
+            NaiveDate::from_ymd_opt(today.0, today.1, today.2)
+                .unwrap();
+        Every::from_str(spec)
+            .and_then(|every| every.timer(today, None))
+            .map_err(|e| e.0)
+    }
+
+    #[test]
+    fn windows_stop_at_their_end() {
+        let today = (2026, 10, 19);
+        assert_eq!(
+            every("every 15m from 09:10 to 17:50", today).unwrap(),
+            "OnCalendar=*-*-* 09:10/15:00\n\
+            OnCalendar=*-*-* 10..16:10/15:00\n\
+            OnCalendar=*-*-* 17:10..40/15:00"
+        );
+        assert_eq!(
+            every("every 15m from 09:10 to 09:50", today).unwrap(),
+            "OnCalendar=*-*-* 09:10..40/15:00"
+        );
+        assert_eq!(
+            every("every 20m from 08:00 to 08:10", today).unwrap(),
+            "OnCalendar=*-*-* 08:00:00"
+        );
+        assert_eq!(
+            every("every 1m from 08:00 to 08:10", today).unwrap(),
+            "OnCalendar=*-*-* 08:00..10:00"
+        );
+        assert_eq!(
+            every("every 2h from 09:00 to 17:00", today).unwrap(),
+            "OnCalendar=*-*-* 09..17/2:00:00"
+        );
+        assert_eq!(
+            every("every 2h from 09:00 to 23:00", today).unwrap(),
+            "OnCalendar=*-*-* 09/2:00:00"
+        );
+    }
+
+    #[test]
+    fn nth_weekdays_in_and_every() {
+        let now = Utc.from_utc_datetime(
+            &NaiveDate::from_ymd_opt(2026, 10, 19)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+        );
+        for (nth, day, calendar) in [
+            ("1st Mon", 2, "Mon *-*-01..07"),
+            ("2. Tue", 10, "Tue *-*-08..14"),
+            ("-1 Fri", 27, "Fri *-*~01..07"),
+            ("last Fri", 27, "Fri *-*~01..07"),
+            ("second-to-last Sun", 22, "Sun *-*~08..14"),
+            ("second to last Sunday", 22, "Sun *-*~08..14"),
+        ] {
+            assert_eq!(
+                In::from_str(&format!("in 1M at {}", nth))
+                    .and_then(|i| i.date(now))
+                    .unwrap_or_else(|e| panic!("{}: {}", nth, e.0)),
+                NaiveDate::from_ymd_opt(2026, 11, day)
+                    .unwrap()
+                    .and_hms_opt(0, 0, 0)
+                    .unwrap(),
+                "{}",
+                nth
+            );
+            assert_eq!(
+                every(
+                    &format!("every month on {} at 9", nth),
+                    (2026, 10, 19)
+                )
+                .unwrap_or_else(|e| panic!("{}: {}", nth, e)),
+                format!("OnCalendar={} 09:00:00", calendar),
+            );
+        }
+        assert!(every(
+            "every month on 2nd Tue from 9",
+            (2026, 10, 19)
+        )
+        .unwrap_err()
+        .contains("together"));
+    }
+
+    #[test]
+    fn until_keeps_the_months() {
+        let today = (2026, 10, 19);
+        assert_eq!(
+            every("every day until 2027-12-31", today).unwrap(),
+            "OnCalendar=2026..2027-*-* 00:00:00"
+        );
+        assert_eq!(
+            every("every day in Jun..Aug until 2027-07-15", today)
+                .unwrap(),
+            "OnCalendar=2026-06..08-* 00:00:00\n\
+            OnCalendar=2027-06-* 00:00:00\n\
+            OnCalendar=2027-07-01..15 00:00:00"
+        );
+        assert_eq!(
+            every("every hour in Nov until 2028-03-31", today)
+                .unwrap(),
+            "OnCalendar=2026..2027-11-* *:00:00"
+        );
+        assert!(every(
+            "every day in Jan,Mar until 2026-12-15",
+            today
+        )
+        .unwrap_err()
+        .contains("never runs"));
+    }
+
+    #[test]
+    fn shorthands_and_aliases() {
+        for (spec, expanded) in [
+            ("mly", "every minute"),
+            ("Mly", "every month"),
+            ("dly at 8", "every day at 8"),
+            ("DAILY at 8", "every day at 8"),
+            ("hourly from 9 to 17", "every hour from 9 to 17"),
+            ("quarterly", "every 3 months"),
+            ("weekdays at 9", "every day on Mon..Fri at 9"),
+            ("end  of MONTH at 17", "every month on last at 17"),
+            ("tomorrow", "in 1d at 0"),
+            ("tomorrow at 9", "in 1d at 9"),
+            ("noon", "every day at 12:00"),
+            ("at midnight", "every day at 00:00"),
+            ("9:30", "every day at 9:30"),
+            ("every day at noon", "every day at 12:00"),
+            ("every 2 days", "every 2 days"),
+            ("MLY", "MLY"),
+            ("fortnightly", "fortnightly"),
+        ] {
+            assert_eq!(expand(spec), expanded, "{}", spec);
+        }
+        let today = (2026, 10, 19);
+        assert_eq!(
+            every(&expand("weekdays at 9"), today).unwrap(),
+            "OnCalendar=Mon..Fri *-*-* 09:00:00"
+        );
+        assert_eq!(
+            every(&expand("end of month at 17"), today).unwrap(),
+            "OnCalendar=*-*~01 17:00:00"
+        );
+    }
+
+    #[test]
+    fn until_in_the_past() {
+        let today = (2026, 10, 19);
+        assert_eq!(
+            every("every day until 2025-03-15", today).unwrap(),
+            "OnCalendar=2025-01..02-* 00:00:00\n\
+            OnCalendar=2025-03-01..15 00:00:00"
+        );
+        let until =
+            Every::from_str("every day until 2025-03-15").unwrap();
+        let today = NaiveDate::from_ymd_opt(2026, 10, 19).unwrap();
+        assert!(until
+            .check_future(today)
+            .unwrap_err()
+            .0
+            .contains("--allow-past"));
+        assert!(until
+            .check_future(today.with_year(2025).unwrap())
+            .is_err());
+        assert!(until
+            .check_future(
+                NaiveDate::from_ymd_opt(2025, 3, 15).unwrap()
+            )
+            .is_ok());
+    }
+
+    #[test]
+    fn zone_before_until() {
+        for spec in [
+            "every day at 9 Europe/Berlin until 2027-01-01",
+            "every day at 9 until 2027-01-01 Europe/Berlin",
+        ] {
+            match Timer::from_str(spec).unwrap() {
+                Timer::Every(every, Some(zone)) => {
+                    assert_eq!(zone, Tz::Europe__Berlin);
+                    assert_eq!(
+                        every.until,
+                        NaiveDate::from_ymd_opt(2027, 1, 1)
+                    );
+                }
+                timer => panic!("{}: {:?}", spec, timer),
+            }
+        }
+        assert!(Timer::from_str(
+            "every day at 9 Mars/Base until 2027-01-01"
+        )
+        .unwrap_err()
+        .0
+        .contains("not a known time zone"));
+    }
+
+    /// The examples of systemd.time(7) and how systemd normalizes them,
+    /// fractional seconds like `05:40:23.4200004/3.1700005` aren't supported
+    #[test]
+    fn systemd_time_calendar_examples() {
+        for (spec, normalized) in [
+            (
+                "Sat,Thu,Mon..Wed,Sat..Sun",
+                "Mon..Thu,Sat,Sun *-*-* 00:00:00",
+            ),
+            ("Mon,Sun 12-*-* 2,1:23", "Mon,Sun 2012-*-* 01,02:23:00"),
+            ("Wed *-1", "Wed *-*-01 00:00:00"),
+            ("Wed..Wed,Wed *-1", "Wed *-*-01 00:00:00"),
+            ("Wed, 17:48", "Wed *-*-* 17:48:00"),
+            (
+                "Wed..Sat,Tue 12-10-15 1:2:3",
+                "Tue..Sat 2012-10-15 01:02:03",
+            ),
+            ("*-*-7 0:0:0", "*-*-07 00:00:00"),
+            ("10-15", "*-10-15 00:00:00"),
+            ("monday *-12-* 17:00", "Mon *-12-* 17:00:00"),
+            (
+                "Mon,Fri *-*-3,1,2 *:30:45",
+                "Mon,Fri *-*-01,02,03 *:30:45",
+            ),
+            ("12,14,13,12:20,10,30", "*-*-* 12,13,14:10,20,30:00"),
+            ("12..14:10,20,30", "*-*-* 12..14:10,20,30:00"),
+            ("03-05 08:05:40", "*-03-05 08:05:40"),
+            ("08:05:40", "*-*-* 08:05:40"),
+            ("05:40", "*-*-* 05:40:00"),
+            ("Sat,Sun 12-05 08:05:40", "Sat,Sun *-12-05 08:05:40"),
+            ("Sat,Sun 08:05:40", "Sat,Sun *-*-* 08:05:40"),
+            ("2003-03-05 05:40", "2003-03-05 05:40:00"),
+            ("2003-02..04-05", "2003-02..04-05 00:00:00"),
+            ("2003-03-05 05:40 UTC", "2003-03-05 05:40:00 UTC"),
+            ("2003-03-05", "2003-03-05 00:00:00"),
+            ("03-05", "*-03-05 00:00:00"),
+            ("hourly", "*-*-* *:00:00"),
+            ("daily", "*-*-* 00:00:00"),
+            ("daily UTC", "*-*-* 00:00:00 UTC"),
+            ("monthly", "*-*-01 00:00:00"),
+            ("weekly", "Mon *-*-* 00:00:00"),
+            (
+                "weekly Pacific/Auckland",
+                "Mon *-*-* 00:00:00 Pacific/Auckland",
+            ),
+            ("yearly", "*-01-01 00:00:00"),
+            ("annually", "*-01-01 00:00:00"),
+            ("*:2/3", "*-*-* *:02/3:00"),
+            ("*-05~03", "*-05~03 00:00:00"),
+            ("Mon *-05~07/1", "Mon *-05~07/1 00:00:00"),
+        ] {
+            assert_eq!(
+                timer(&format!("OnCalendar={}", spec)),
+                format!("OnCalendar={}", normalized)
+            );
+        }
+    }
+
+    /// The time spans of systemd.time(7) in microseconds
+    #[test]
+    fn systemd_time_timespan_examples() {
+        const SEC: i64 = 1_000_000;
+        for (span, micros) in [
+            ("2 h", 2 * 3600 * SEC),
+            ("2hours", 2 * 3600 * SEC),
+            ("48hr", 48 * 3600 * SEC),
+            ("1y 12month", 2 * 31_557_600 * SEC),
+            ("55s500ms", 55_500_000),
+            ("300ms20s 5day", (5 * 86400 + 20) * SEC + 300_000),
+            ("90", 90 * SEC),
+            ("1.5min", 90 * SEC),
+            ("10µs", 10),
+        ] {
+            assert_eq!(
+                InDuration::from_str(span).unwrap().micros(),
+                Some(micros),
+                "{}",
+                span
+            );
+        }
+        let infinity = InDuration::from_str("infinity").unwrap();
+        assert_eq!(infinity.micros(), None);
+        assert_eq!(infinity.to_string(), "infinity");
+        assert_eq!(timer("SinceBoot infinity"), "OnBootSec=infinity");
+        assert_eq!(
+            timer("SinceBoot 300ms20s 5day"),
+            "OnBootSec=5d 20s 300ms"
+        );
+        assert!(InDuration::from_str("5 fortnights").is_err());
+    }
+
+    #[test]
+    fn in_at_in_any_case() {
+        let in_at = |spec: &str| {
+            let now = Utc.from_utc_datetime(
+                &NaiveDate::from_ymd_opt(2026, 10, 19)
+                    .unwrap()
+                    .and_hms_opt(12, 0, 0)
+                    .unwrap(),
+            );
+            In::from_str(spec)
+                .and_then(|i| i.date(now))
+                .unwrap_or_else(|e| panic!("{}: {}", spec, e.0))
+                .to_string()
+        };
+        assert_eq!(in_at("In 5d At 4am"), "2026-10-24 04:00:00");
+        assert_eq!(in_at("IN 5d AT 4am"), "2026-10-24 04:00:00");
+        assert_eq!(in_at("I 5d A 4am"), "2026-10-24 04:00:00");
+        assert_eq!(in_at("in 1d at 4pm"), "2026-10-20 16:00:00");
+        assert_eq!(in_at("in 1d at 12am"), "2026-10-20 00:00:00");
+        assert_eq!(in_at("in 1d at 12pm"), "2026-10-20 12:00:00");
+        assert_eq!(in_at("in 1d at noon"), "2026-10-20 12:00:00");
+        assert_eq!(in_at("in 1d at midnight"), "2026-10-20 00:00:00");
+        assert_eq!(in_at("in 1d at 16h30"), "2026-10-20 16:30:00");
+        assert_eq!(in_at("In 2d At 4:15 PM"), "2026-10-21 16:15:00");
+    }
+}
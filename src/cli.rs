@@ -1,20 +1,34 @@
-use clap::{AppSettings, Clap};
-use std::default::Default;
+use clap::{AppSettings, ArgSettings, Clap};
 use std::path::PathBuf;
 
 #[path = "calendar.rs"]
 pub mod calendar;
-use calendar::Timer;
+use calendar::{DstPolicy, InDuration, Timer};
+
+#[path = "month.rs"]
+pub mod month;
+
+#[path = "names.rs"]
+pub mod names;
+
+#[path = "directive.rs"]
+pub mod directive;
+use directive::{
+    Directive, ListenAddress, PathTrigger, SocketKind, UnitName,
+};
 
 #[derive(Debug, Clap)]
 #[clap(
     name = "autod",
     version = "0.1",
     author = "Roland F. <important@van-fredenhagen.de>",
-    bin_name = "autod"
+    bin_name = "autod",
+    setting = AppSettings::SubcommandsNegateReqs
 )] //, global_setting = AppSettings::DisableHelpSubcommand)]
 pub struct Opt {
-    pub command: PathBuf,
+    /// The command to run, not needed for `verify`, `restore` and `apply`
+    #[clap(setting = ArgSettings::Required)]
+    pub command: Option<PathBuf>,
 
     #[clap(subcommand)]
     pub target: Option<Target>,
@@ -30,6 +44,17 @@ pub struct Opt {
     #[clap(long, short, global = true)]
     pub overwrite: bool,
 
+    /// Shows how the files on disk would change instead of writing them
+    #[clap(long, global = true)]
+    pub diff: bool,
+
+    /// Exits with an error if the files on disk are not up to date
+    ///
+    /// Nothing is written, use this to check that units are in sync with
+    /// the command creating them, e.g. in a config repository.
+    #[clap(long, global = true)]
+    pub check_only: bool,
+
     /// Creates a oneshot service
     ///
     /// This means, when starting the service, while it is still running,
@@ -54,22 +79,179 @@ pub struct Opt {
     /// Use this if you are storing your service files on a windows partition
     #[clap(long, short, global = true)]
     pub windows: bool,
+
+    /// Sets an additional directive, e.g. `Service.Restart=on-failure`
+    ///
+    /// Can be given multiple times, the first occurrence of a key replaces
+    /// what autod would write, further ones are appended. [Unit] and
+    /// [Install] directives go to the service file, prefix them with
+    /// `timer.` to target the timer file instead, e.g.
+    /// `timer.Unit.Description=Daily backup`.
+    #[clap(long, global = true, number_of_values = 1)]
+    pub set: Vec<Directive>,
+
+    /// Argument passed to the command, can be given multiple times
+    #[clap(
+        long = "arg",
+        short,
+        global = true,
+        number_of_values = 1,
+        allow_hyphen_values = true
+    )]
+    pub args: Vec<String>,
+
+    /// Creates a template service, e.g. `backup@.service`
+    ///
+    /// Use `%i` or `%I` in --arg for the (unescaped) instance name, or `%f`
+    /// for a path, if none is used, the instance is passed as last
+    /// argument.
+    #[clap(long, short, global = true)]
+    pub template: bool,
+
+    /// Enables an instance of the template, can be given multiple times
+    ///
+    /// Absolute paths are escaped like `systemd-escape --path` does, so
+    /// `-i /home/me` enables `name@home-me`.
+    #[clap(long, short, global = true, number_of_values = 1)]
+    pub instance: Vec<String>,
+
+    /// Runs a timer missed while the machine was off on the next boot
+    #[clap(long, global = true)]
+    pub persistent: bool,
+
+    /// How precise the timer has to elapse, e.g. `1s`, systemd uses 1m
+    #[clap(long, global = true)]
+    pub accuracy: Option<InDuration>,
+
+    /// Delays every elapse by a random time up to this, e.g. `30m`
+    ///
+    /// Use this to avoid many machines running the job at the same time.
+    #[clap(long, global = true)]
+    pub random_delay: Option<InDuration>,
+
+    /// Keeps the random delay of --random-delay the same for every elapse
+    #[clap(long, global = true)]
+    pub fixed_random_delay: bool,
+
+    /// Wakes the system from suspend to run the timer
+    #[clap(long, global = true)]
+    pub wake_system: bool,
+
+    /// Whether an elapsed timer stays loaded, systemd defaults to true
+    #[clap(long, global = true)]
+    pub remain_after_elapse: Option<bool>,
+
+    /// Resolves times skipped or repeated by daylight saving time
+    ///
+    /// `earliest` picks the first of two repeated times and moves skipped
+    /// times back, `latest` picks the second and moves them forward,
+    /// `error` refuses to create the timer.
+    #[clap(long, global = true, default_value = "earliest")]
+    pub dst: DstPolicy,
+
+    /// Creates timers for fixed dates that already passed
+    #[clap(long, global = true)]
+    pub allow_past: bool,
+
+    /// Explains when the timer runs in plain English instead of creating it
+    #[clap(long, global = true)]
+    pub explain: bool,
+
+    /// Checks the units with systemd-analyze
+    ///
+    /// Every OnCalendar= value is run through `systemd-analyze calendar`,
+    /// which has to accept it and agree with autod on the next elapse, the
+    /// written files through `systemd-analyze verify`.
+    #[clap(long, global = true)]
+    pub check: bool,
+
+    /// The systemd-analyze binary --check runs
+    #[clap(long, global = true, default_value = "systemd-analyze")]
+    pub systemd_analyze: PathBuf,
+
+    /// Starts the service after these units are up
+    #[clap(long, global = true, number_of_values = 1)]
+    pub after: Vec<UnitName>,
+
+    /// Units that are started with the service, which fails if they fail
+    #[clap(long, global = true, number_of_values = 1)]
+    pub requires: Vec<UnitName>,
+
+    /// Units that are started with the service
+    #[clap(long, global = true, number_of_values = 1)]
+    pub wants: Vec<UnitName>,
+
+    /// Like --requires, but also stops the service when they stop
+    #[clap(long, global = true, number_of_values = 1)]
+    pub binds_to: Vec<UnitName>,
+
+    /// Stops and restarts the service together with these units
+    #[clap(long, global = true, number_of_values = 1)]
+    pub part_of: Vec<UnitName>,
 }
 
-#[derive(Debug, Clap)]
+#[derive(Debug, Default, Clap)]
 pub enum Target {
     When {
         timer: Timer,
     },
     On {
-        event: String,
+        event: UnitName,
+    },
+    /// Runs the command when a file or directory changes
+    Watch {
+        path: PathBuf,
+
+        /// What to watch for
+        ///
+        /// `changed` triggers when a file is closed after writing,
+        /// `modified` on every write, `exists` when the path is created,
+        /// `glob` when any file matches the glob in path and `not-empty`
+        /// when a file is put into the directory.
+        #[clap(long, default_value = "changed")]
+        trigger: PathTrigger,
+    },
+    /// Starts the command on demand when the socket is used
+    Listen {
+        /// A port, ip:port, [ipv6]:port or absolute socket path
+        address: ListenAddress,
+
+        /// The socket kind: stream, datagram or fifo
+        #[clap(long, default_value = "stream")]
+        kind: SocketKind,
+
+        /// Starts a new instance for every connection
+        ///
+        /// The service is created as template and gets the connection as
+        /// stdin and stdout, like with inetd.
+        #[clap(long)]
+        accept: bool,
+    },
+    /// Runs the command once the unit is up, e.g. network-online.target
+    After {
+        unit: UnitName,
+    },
+    /// Checks unit files for mistakes, like `systemd-analyze verify`
+    Verify {
+        #[clap(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Restores the unit files of a service from the backup kept when
+    /// they were overwritten, restoring again undoes this
+    Restore {
+        name: String,
+    },
+    /// Creates, updates and removes units to match the jobs of a config
+    /// file, printing the plan first
+    ///
+    /// --diff shows the changes and --check-only fails if there are any,
+    /// both without touching the files. Files autod apply didn't write are
+    /// only replaced with -o.
+    Apply {
+        #[clap(default_value = "autod.toml")]
+        file: PathBuf,
     },
     #[clap(setting(AppSettings::Hidden))]
+    #[default]
     No,
 }
-
-impl Default for Target {
-    fn default() -> Self {
-        Target::No
-    }
-}
@@ -0,0 +1,335 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use crate::cli::calendar::ParseError;
+
+/// Keys naming the trigger of a job, the subcommand on the command line
+const TRIGGERS: [&str; 4] = ["when", "on", "watch", "listen"];
+
+/// Options of `autod apply` itself, which jobs can't set
+const APPLY_ONLY: [&str; 8] = [
+    "name",
+    "print",
+    "overwrite",
+    "diff",
+    "check-only",
+    "explain",
+    "check",
+    "systemd-analyze",
+];
+
+/// Options taking `true` or `false` instead of being a flag
+const BOOL_VALUES: [&str; 1] = ["remain-after-elapse"];
+
+/// Options of timers, defaults for them are skipped for other jobs
+const TIMER_OPTIONS: [&str; 8] = [
+    "persistent",
+    "accuracy",
+    "random-delay",
+    "fixed-random-delay",
+    "wake-system",
+    "remain-after-elapse",
+    "dst",
+    "allow-past",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Bool(bool),
+    Array(Vec<Value>),
+}
+
+impl Display for Value {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => write!(f, "{}", s),
+            Value::Integer(i) => write!(f, "{}", i),
+            Value::Float(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Array(values) => {
+                let values: Vec<String> =
+                    values.iter().map(Value::to_string).collect();
+                write!(f, "[{}]", values.join(", "))
+            }
+        }
+    }
+}
+
+/// A `[name]` table of the config file, creating one service
+#[derive(Debug)]
+pub struct Job {
+    pub name: String,
+    pub options: Vec<(String, Value)>,
+}
+
+/// The jobs of an `autod.toml`
+///
+/// Every table is a job, the keys outside of tables are defaults for every
+/// job. Their values are strings, numbers, booleans and arrays of them.
+#[derive(Debug, Default)]
+pub struct Config {
+    pub defaults: Vec<(String, Value)>,
+    pub jobs: Vec<Job>,
+}
+
+impl Config {
+    /// The command line creating every job, as `autod` would be called
+    pub fn command_lines(
+        &self,
+    ) -> Result<Vec<(&str, Vec<String>)>, ParseError> {
+        self.jobs
+            .iter()
+            .map(|job| {
+                self.command_line(job)
+                    .map(|line| (job.name.as_str(), line))
+                    .map_err(|e| {
+                        ParseError(format!("[{}] {}", job.name, e.0))
+                    })
+            })
+            .collect()
+    }
+
+    fn command_line(
+        &self,
+        job: &Job,
+    ) -> Result<Vec<String>, ParseError> {
+        // Keys of the job replace the defaults
+        let timer = job.options.iter().any(|(key, _)| key == "when");
+        let defaults = self.defaults.iter().filter(|(key, _)| {
+            timer
+                || !TIMER_OPTIONS
+                    .contains(&key.replace('_', "-").as_str())
+        });
+        let mut options: Vec<(String, &Value)> = Vec::new();
+        for (key, value) in defaults.chain(&job.options) {
+            let key = key.replace('_', "-");
+            options.retain(|(k, _)| *k != key);
+            options.push((key, value));
+        }
+
+        let mut command = None;
+        let mut trigger = None;
+        let mut args = Vec::new();
+        for (key, value) in options {
+            match key.as_str() {
+                "command" => command = Some(single(&key, value)?),
+                key if TRIGGERS.contains(&key) => {
+                    if let Some((other, _)) = trigger {
+                        Err(ParseError(format!(
+                            "has the triggers {} and {}, a job can only \
+                            have one",
+                            other, key
+                        )))?;
+                    }
+                    trigger =
+                        Some((key.to_owned(), single(key, value)?));
+                }
+                key if APPLY_ONLY.contains(&key) => {
+                    Err(ParseError(if key == "name" {
+                        String::from(
+                            "sets a name, the name of the table is used",
+                        )
+                    } else {
+                        format!(
+                            "sets {}, which only applies to `autod apply`",
+                            key
+                        )
+                    }))?
+                }
+                key => {
+                    let option = match key {
+                        "args" => "arg",
+                        key => key,
+                    };
+                    let values = match value {
+                        Value::Array(values) => {
+                            values.iter().collect()
+                        }
+                        value => vec![value],
+                    };
+                    for value in values {
+                        match value {
+                            Value::Bool(true) => {
+                                args.push(format!("--{}", option))
+                            }
+                            Value::Bool(false)
+                                if !BOOL_VALUES.contains(&option) => {
+                            }
+                            Value::Array(_) => {
+                                Err(ParseError(format!(
+                                    "{} can't hold nested arrays",
+                                    key
+                                )))?
+                            }
+                            value => args.push(format!(
+                                "--{}={}",
+                                option, value
+                            )),
+                        }
+                    }
+                }
+            }
+        }
+
+        let command = command.ok_or_else(|| {
+            ParseError(String::from("has no command to run"))
+        })?;
+        let mut line = vec![String::from("autod"), command];
+        if let Some((trigger, value)) = trigger {
+            line.push(trigger);
+            line.push(value);
+        }
+        line.extend(args);
+        line.push(format!("--name={}", job.name));
+        Ok(line)
+    }
+}
+
+/// The value of a key that takes a single string or number
+fn single(key: &str, value: &Value) -> Result<String, ParseError> {
+    match value {
+        Value::String(_) | Value::Integer(_) | Value::Float(_) => {
+            Ok(value.to_string())
+        }
+        _ => Err(ParseError(format!(
+            "{} needs a string, not {}",
+            key, value
+        ))),
+    }
+}
+
+/// Converts a TOML value of `key`, dates are kept as written
+fn value(
+    key: &str,
+    option: toml::Value,
+) -> Result<Value, ParseError> {
+    Ok(match option {
+        toml::Value::String(s) => Value::String(s),
+        toml::Value::Integer(i) => Value::Integer(i),
+        toml::Value::Float(f) => Value::Float(f),
+        toml::Value::Boolean(b) => Value::Bool(b),
+        toml::Value::Datetime(date) => {
+            Value::String(date.to_string())
+        }
+        toml::Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|v| value(key, v))
+                .collect::<Result<_, _>>()?,
+        ),
+        toml::Value::Table(_) => Err(ParseError(format!(
+            "{} can't be a table, only the jobs are tables",
+            key
+        )))?,
+    })
+}
+
+impl FromStr for Config {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let table: toml::value::Table = toml::from_str(s)
+            .map_err(|e| ParseError(e.to_string()))?;
+        let mut config = Config::default();
+        for (key, option) in table {
+            match option {
+                toml::Value::Table(options) => {
+                    let options = options
+                        .into_iter()
+                        .map(|(k, v)| Ok((k.clone(), value(&k, v)?)))
+                        .collect::<Result<_, ParseError>>()
+                        .map_err(|e| {
+                            ParseError(format!("[{}] {}", key, e.0))
+                        })?;
+                    config.jobs.push(Job { name: key, options });
+                }
+                toml::Value::Array(tables)
+                    if !tables.is_empty()
+                        && tables.iter().all(toml::Value::is_table) =>
+                {
+                    Err(ParseError(format!(
+                        "[[{}]] is an array of tables, use a [name] table \
+                        per job",
+                        key
+                    )))?
+                }
+                option => {
+                    let option = value(&key, option)?;
+                    config.defaults.push((key, option));
+                }
+            }
+        }
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tables_are_jobs_with_the_defaults() {
+        let config = Config::from_str(
+            r#"
+            persistent = true
+            output-dir = "units"
+
+            [backup]
+            command = "backup.sh"
+            args = ["--fast", "/home"]
+            when = "daily at 3"
+            accuracy = 1.5
+
+            [sync]
+            command = "sync.sh"
+            on = "network-online.target"
+            "#,
+        )
+        .unwrap();
+        let lines = config.command_lines().unwrap();
+        assert_eq!(
+            lines[0],
+            (
+                "backup",
+                vec![
+                    "autod",
+                    "backup.sh",
+                    "when",
+                    "daily at 3",
+                    "--persistent",
+                    "--output-dir=units",
+                    "--arg=--fast",
+                    "--arg=/home",
+                    "--accuracy=1.5",
+                    "--name=backup",
+                ]
+                .into_iter()
+                .map(String::from)
+                .collect()
+            )
+        );
+        assert_eq!(
+            lines[1].1.join(" "),
+            "autod sync.sh on network-online.target --output-dir=units \
+            --name=sync"
+        );
+    }
+
+    #[test]
+    fn nested_tables_are_refused() {
+        for (toml, error) in [
+            ("[[backup]]\ncommand = \"a\"", "array of tables"),
+            (
+                "[backup.timer]\nwhen = \"daily\"",
+                "[backup] timer can't",
+            ),
+            ("[backup]\ncommand = ", "line 2"),
+        ] {
+            let e = Config::from_str(toml).unwrap_err().0;
+            assert!(e.contains(error), "{}: {}", toml, e);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Write;
+
+/// Lines of unchanged context around every change
+const CONTEXT: usize = 3;
+
+enum Line<'a> {
+    Same(&'a str),
+    Removed(&'a str),
+    Added(&'a str),
+}
+
+/// The lines of `old` and `new`, matched along their longest common
+/// subsequence
+fn lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<Line<'a>> {
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] {
+                common[i + 1][j + 1] + 1
+            } else {
+                common[i + 1][j].max(common[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push(Line::Same(old[i]));
+            i += 1;
+            j += 1;
+        } else if i < old.len()
+            && (j == new.len()
+                || common[i + 1][j] >= common[i][j + 1])
+        {
+            lines.push(Line::Removed(old[i]));
+            i += 1;
+        } else {
+            lines.push(Line::Added(new[j]));
+            j += 1;
+        }
+    }
+    lines
+}
+
+/// A unified diff from `old` to `new` like `diff -u` writes it, empty if
+/// they are the same
+pub fn unified(
+    old: &str,
+    new: &str,
+    old_name: &str,
+    new_name: &str,
+) -> String {
+    // With their line breaks, so a missing one at the end is a change
+    let old: Vec<&str> = old.split_inclusive('\n').collect();
+    let new: Vec<&str> = new.split_inclusive('\n').collect();
+    let lines = lines(&old, &new);
+
+    // Changed lines with their context, merged where they touch
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for (i, _) in lines
+        .iter()
+        .enumerate()
+        .filter(|(_, line)| !matches!(line, Line::Same(_)))
+    {
+        let (start, end) = (
+            i.saturating_sub(CONTEXT),
+            (i + CONTEXT + 1).min(lines.len()),
+        );
+        match hunks.last_mut() {
+            Some((_, last)) if *last >= start => *last = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    if hunks.is_empty() {
+        return String::new();
+    }
+
+    let mut diff = format!("--- {}\n+++ {}\n", old_name, new_name);
+    for (start, end) in hunks {
+        let before = &lines[..start];
+        let hunk = &lines[start..end];
+        let count = |lines: &[Line], added: bool| {
+            lines
+                .iter()
+                .filter(|line| match line {
+                    Line::Same(_) => true,
+                    Line::Removed(_) => !added,
+                    Line::Added(_) => added,
+                })
+                .count()
+        };
+        // An empty range starts at the line before it
+        let range = |before: usize, count: usize| {
+            format!(
+                "{},{}",
+                if count == 0 { before } else { before + 1 },
+                count
+            )
+        };
+        writeln!(
+            diff,
+            "@@ -{} +{} @@",
+            range(count(before, false), count(hunk, false)),
+            range(count(before, true), count(hunk, true))
+        )
+        .unwrap();
+        for line in hunk {
+            let (prefix, line) = match line {
+                Line::Same(line) => (' ', line),
+                Line::Removed(line) => ('-', line),
+                Line::Added(line) => ('+', line),
+            };
+            match line.strip_suffix('\n') {
+                Some(line) => writeln!(diff, "{}{}", prefix, line),
+                None => writeln!(
+                    diff,
+                    "{}{}\n\\ No newline at end of file",
+                    prefix, line
+                ),
+            }
+            .unwrap();
+        }
+    }
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str) -> String {
+        unified(old, new, "a", "b")
+    }
+
+    #[test]
+    fn identical() {
+        assert_eq!(diff("", ""), "");
+        assert_eq!(diff("a\nb\n", "a\nb\n"), "");
+    }
+
+    #[test]
+    fn insertion() {
+        assert_eq!(
+            diff("", "a\nb\n"),
+            "--- a\n+++ b\n@@ -0,0 +1,2 @@\n+a\n+b\n"
+        );
+        assert_eq!(
+            diff("a\nc\n", "a\nb\nc\n"),
+            "--- a\n+++ b\n@@ -1,2 +1,3 @@\n a\n+b\n c\n"
+        );
+    }
+
+    #[test]
+    fn deletion() {
+        assert_eq!(
+            diff("a\nb\n", ""),
+            "--- a\n+++ b\n@@ -1,2 +0,0 @@\n-a\n-b\n"
+        );
+        assert_eq!(
+            diff("a\nb\nc\n", "a\nc\n"),
+            "--- a\n+++ b\n@@ -1,3 +1,2 @@\n a\n-b\n c\n"
+        );
+    }
+
+    #[test]
+    fn change_in_the_middle() {
+        let old: String =
+            (1..=20).map(|n| format!("{}\n", n)).collect();
+        let new =
+            old.replace("\n8\n", "\neight\n").replace("20\n", "");
+        assert_eq!(
+            diff(&old, &new),
+            "--- a\n+++ b\n\
+            @@ -5,7 +5,7 @@\n 5\n 6\n 7\n-8\n+eight\n 9\n 10\n 11\n\
+            @@ -17,4 +17,3 @@\n 17\n 18\n 19\n-20\n"
+        );
+        // Up to twice the context between changes shares a hunk
+        let new =
+            old.replace("\n8\n", "\neight\n").replace("15\n", "");
+        assert_eq!(diff(&old, &new).matches("@@ -").count(), 1);
+        let new =
+            old.replace("\n8\n", "\neight\n").replace("16\n", "");
+        assert_eq!(diff(&old, &new).matches("@@ -").count(), 2);
+    }
+
+    #[test]
+    fn missing_newline_at_the_end() {
+        assert_eq!(
+            diff("a\nb", "a\nb\n"),
+            "--- a\n+++ b\n@@ -1,2 +1,2 @@\n a\n-b\n\
+            \\ No newline at end of file\n+b\n"
+        );
+        assert_eq!(
+            diff("a\n", "a\nb"),
+            "--- a\n+++ b\n@@ -1,1 +1,2 @@\n a\n+b\n\
+            \\ No newline at end of file\n"
+        );
+    }
+}
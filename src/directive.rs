@@ -0,0 +1,1151 @@
+use std::fmt::{Display, Formatter};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+use super::calendar::ParseError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum UnitKind {
+    Service,
+    Timer,
+    Path,
+    Socket,
+}
+
+impl UnitKind {
+    /// The section holding the options specific to this kind of unit
+    pub fn section(&self) -> Section {
+        match self {
+            UnitKind::Service => Section::Service,
+            UnitKind::Timer => Section::Timer,
+            UnitKind::Path => Section::Path,
+            UnitKind::Socket => Section::Socket,
+        }
+    }
+}
+
+impl Display for UnitKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                UnitKind::Service => "service",
+                UnitKind::Timer => "timer",
+                UnitKind::Path => "path",
+                UnitKind::Socket => "socket",
+            }
+        )
+    }
+}
+
+impl FromStr for UnitKind {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "service" => Ok(UnitKind::Service),
+            "timer" => Ok(UnitKind::Timer),
+            "path" => Ok(UnitKind::Path),
+            "socket" => Ok(UnitKind::Socket),
+            _ => Err(ParseError(format!(
+                "`{}` is not a unit file autod creates, use `service`, \
+                `timer`, `path` or `socket`",
+                s
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Section {
+    Unit,
+    Service,
+    Timer,
+    Path,
+    Socket,
+    Install,
+}
+
+impl Section {
+    const ALL: [Section; 6] = [
+        Section::Unit,
+        Section::Service,
+        Section::Timer,
+        Section::Path,
+        Section::Socket,
+        Section::Install,
+    ];
+
+    /// The unit file this section is written to, if the section alone
+    /// decides that
+    pub fn kind(&self) -> Option<UnitKind> {
+        match self {
+            Section::Service => Some(UnitKind::Service),
+            Section::Timer => Some(UnitKind::Timer),
+            Section::Path => Some(UnitKind::Path),
+            Section::Socket => Some(UnitKind::Socket),
+            Section::Unit | Section::Install => None,
+        }
+    }
+
+    fn known(&self) -> impl Iterator<Item = &'static str> {
+        let tables: &[&[&str]] = match self {
+            Section::Unit => &[UNIT],
+            Section::Service => {
+                &[SERVICE, EXEC, KILL, RESOURCE_CONTROL]
+            }
+            Section::Timer => &[TIMER],
+            Section::Path => &[PATH],
+            Section::Socket => {
+                &[SOCKET, EXEC, KILL, RESOURCE_CONTROL]
+            }
+            Section::Install => &[INSTALL],
+        };
+        tables.iter().flat_map(|table| table.iter().copied())
+    }
+
+    fn knows(&self, key: &str) -> bool {
+        self.known().any(|known| known == key)
+    }
+
+    /// Fails for keys systemd does not know in this section, suggesting
+    /// the right section or spelling, `X-` keys are left to the user
+    pub fn check_key(&self, key: &str) -> Result<(), ParseError> {
+        if self.knows(key) || key.starts_with("X-") {
+            return Ok(());
+        }
+        let elsewhere =
+            Section::ALL.iter().find(|other| other.knows(key));
+        Err(ParseError(match elsewhere {
+            Some(other) => format!(
+                "`{}` belongs in the [{}] section, not [{}]",
+                key, other, self
+            ),
+            None => format!(
+                "`{}` is not a known directive of the [{}] section{}",
+                key,
+                self,
+                suggestion(key, self.known().map(String::from))
+            ),
+        }))
+    }
+}
+
+impl Display for Section {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Section::Unit => "Unit",
+                Section::Service => "Service",
+                Section::Timer => "Timer",
+                Section::Path => "Path",
+                Section::Socket => "Socket",
+                Section::Install => "Install",
+            }
+        )
+    }
+}
+
+impl FromStr for Section {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Section::ALL
+            .iter()
+            .find(|section| {
+                section.to_string().eq_ignore_ascii_case(s)
+            })
+            .copied()
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "`{}` is not a section autod can write to{}",
+                    s,
+                    suggestion(
+                        s,
+                        Section::ALL.iter().map(|s| s.to_string())
+                    )
+                ))
+            })
+    }
+}
+
+/// A single `--set [unit.]Section.Key=Value` directive
+#[derive(Debug)]
+pub struct Directive {
+    pub kind: UnitKind,
+    pub section: Section,
+    pub key: String,
+    pub value: String,
+}
+
+impl FromStr for Directive {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (path, value) = s.split_once('=').ok_or_else(|| {
+            ParseError(format!(
+                "`{}` is missing a value, use Section.Key=Value",
+                s
+            ))
+        })?;
+        let mut parts = path.trim().rsplitn(3, '.');
+        let key = parts.next().unwrap();
+        let (section, kind) = match (parts.next(), parts.next()) {
+            (Some(section), kind) => (
+                Section::from_str(section)?,
+                kind.map(UnitKind::from_str).transpose()?,
+            ),
+            (None, _) => Err(ParseError(format!(
+                "`{}` is missing a section, use Section.Key=Value",
+                s
+            )))?,
+        };
+
+        let kind = match (section.kind(), kind) {
+            (Some(implied), Some(kind)) if implied != kind => {
+                Err(ParseError(format!(
+                    "The {} file has no [{}] section",
+                    kind, section
+                )))?
+            }
+            (Some(kind), _) | (None, Some(kind)) => kind,
+            (None, None) => UnitKind::Service,
+        };
+
+        // Unknown keys are only warned about once the unit is generated,
+        // the tables can't know every directive of every systemd version
+        Ok(Self {
+            kind,
+            section,
+            key: key.to_owned(),
+            value: value.trim().to_owned(),
+        })
+    }
+}
+
+/// The condition a path unit watches for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathTrigger {
+    Changed,
+    Modified,
+    Exists,
+    ExistsGlob,
+    DirectoryNotEmpty,
+}
+
+impl Display for PathTrigger {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                PathTrigger::Changed => "PathChanged",
+                PathTrigger::Modified => "PathModified",
+                PathTrigger::Exists => "PathExists",
+                PathTrigger::ExistsGlob => "PathExistsGlob",
+                PathTrigger::DirectoryNotEmpty => "DirectoryNotEmpty",
+            }
+        )
+    }
+}
+
+impl FromStr for PathTrigger {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "changed" | "pathchanged" => Ok(PathTrigger::Changed),
+            "modified" | "pathmodified" => Ok(PathTrigger::Modified),
+            "exists" | "pathexists" => Ok(PathTrigger::Exists),
+            "glob" | "pathexistsglob" => Ok(PathTrigger::ExistsGlob),
+            "not-empty" | "directorynotempty" => {
+                Ok(PathTrigger::DirectoryNotEmpty)
+            }
+            _ => Err(ParseError(format!(
+                "`{}` is not a path trigger, use changed, modified, \
+                exists, glob or not-empty",
+                s
+            ))),
+        }
+    }
+}
+
+/// The kind of socket a socket unit listens on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SocketKind {
+    Stream,
+    Datagram,
+    Fifo,
+}
+
+impl Display for SocketKind {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                SocketKind::Stream => "ListenStream",
+                SocketKind::Datagram => "ListenDatagram",
+                SocketKind::Fifo => "ListenFIFO",
+            }
+        )
+    }
+}
+
+impl FromStr for SocketKind {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "stream" | "tcp" => Ok(SocketKind::Stream),
+            "datagram" | "udp" => Ok(SocketKind::Datagram),
+            "fifo" => Ok(SocketKind::Fifo),
+            _ => Err(ParseError(format!(
+                "`{}` is not a socket kind, use stream, datagram or fifo",
+                s
+            ))),
+        }
+    }
+}
+
+/// An address a socket unit listens on
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListenAddress {
+    Port(u16),
+    Inet(SocketAddr),
+    Unix(PathBuf),
+    Abstract(String),
+}
+
+impl ListenAddress {
+    pub fn port(&self) -> Option<u16> {
+        match self {
+            ListenAddress::Port(port) => Some(*port),
+            ListenAddress::Inet(addr) => Some(addr.port()),
+            _ => None,
+        }
+    }
+}
+
+impl Display for ListenAddress {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ListenAddress::Port(port) => write!(f, "{}", port),
+            ListenAddress::Inet(addr) => write!(f, "{}", addr),
+            ListenAddress::Unix(path) => {
+                write!(f, "{}", path.display())
+            }
+            ListenAddress::Abstract(name) => write!(f, "@{}", name),
+        }
+    }
+}
+
+impl FromStr for ListenAddress {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let address = match s {
+            _ if s.starts_with('/') => ListenAddress::Unix(s.into()),
+            _ if s.starts_with('@') && s.len() > 1 => {
+                ListenAddress::Abstract(s[1..].to_owned())
+            }
+            _ if !s.is_empty() && s.chars().all(|c| c.is_ascii_digit()) => {
+                ListenAddress::Port(u16::from_str(s).map_err(|_| {
+                    ParseError(format!(
+                        "`{}` is not a valid port, use 1-65535",
+                        s
+                    ))
+                })?)
+            }
+            _ => ListenAddress::Inet(SocketAddr::from_str(s).map_err(
+                |_| {
+                    ParseError(match s.rsplit_once(':') {
+                        Some((host, port)) if !host.contains(':') => {
+                            format!(
+                                "`{}` is not an IP address, systemd can \
+                                not listen on host names, e.g. use \
+                                127.0.0.1:{}",
+                                host, port
+                            )
+                        }
+                        _ => format!(
+                            "`{}` is not a port, ip:port, [ipv6]:port or \
+                            absolute socket path",
+                            s
+                        ),
+                    })
+                },
+            )?),
+        };
+        if address.port() == Some(0) {
+            Err(ParseError(String::from(
+                "Port 0 is not valid for a socket unit, use 1-65535",
+            )))?
+        }
+        Ok(address)
+    }
+}
+
+/// A syntactically valid unit name like `network-online.target` or
+/// `getty@tty1.service`
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitName(String);
+
+impl UnitName {
+    pub fn suffix(&self) -> &str {
+        self.0.rsplit('.').next().unwrap()
+    }
+}
+
+impl Display for UnitName {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl FromStr for UnitName {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (prefix, suffix) =
+            s.rsplit_once('.').ok_or_else(|| {
+                ParseError(format!(
+                    "`{}` is missing a unit type, e.g. `{}.target`",
+                    s, s
+                ))
+            })?;
+        if !UNIT_TYPES.contains(&suffix) {
+            Err(ParseError(format!(
+                "`.{}` in `{}` is not a known unit type{}",
+                suffix,
+                s,
+                suggestion(
+                    &format!(".{}", suffix),
+                    UNIT_TYPES.iter().map(|t| format!(".{}", t))
+                )
+            )))?
+        }
+        if s.len() > 255 {
+            Err(ParseError(format!(
+                "`{}` is longer than 255 characters",
+                s
+            )))?
+        }
+        let (name, instance) = match prefix.split_once('@') {
+            Some((name, instance)) => (name, Some(instance)),
+            None => (prefix, None),
+        };
+        if name.is_empty() {
+            Err(ParseError(format!(
+                "`{}` has an empty unit name",
+                s
+            )))?
+        }
+        if let Some(c) = name
+            .chars()
+            .chain(instance.unwrap_or_default().chars())
+            .find(|c| {
+                !(c.is_ascii_alphanumeric() || ":-_.\\".contains(*c))
+            })
+        {
+            Err(ParseError(format!(
+                "`{}` contains `{}`, which is not allowed in unit names",
+                s, c
+            )))?
+        }
+        Ok(Self(s.to_owned()))
+    }
+}
+
+/// Formats a ", did you mean `...`?" hint for the closest candidate,
+/// or nothing if none is close enough
+fn suggestion(
+    input: &str,
+    candidates: impl Iterator<Item = String>,
+) -> String {
+    candidates
+        .map(|c| (distance(input, &c), c))
+        .filter(|(d, c)| *d <= (c.len() / 3).max(2))
+        .min_by_key(|(d, _)| *d)
+        .map_or_else(String::new, |(_, c)| {
+            format!(", did you mean `{}`?", c)
+        })
+}
+
+/// Case insensitive Levenshtein distance
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.to_lowercase().chars().collect();
+    let b: Vec<char> = b.to_lowercase().chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.iter().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+const UNIT_TYPES: &[&str] = &[
+    "service",
+    "socket",
+    "device",
+    "mount",
+    "automount",
+    "swap",
+    "target",
+    "path",
+    "timer",
+    "slice",
+    "scope",
+];
+
+const UNIT: &[&str] = &[
+    "Description",
+    "Documentation",
+    "Wants",
+    "Requires",
+    "Requisite",
+    "BindsTo",
+    "PartOf",
+    "Upholds",
+    "Conflicts",
+    "Before",
+    "After",
+    "OnFailure",
+    "OnSuccess",
+    "PropagatesReloadTo",
+    "ReloadPropagatedFrom",
+    "PropagatesStopTo",
+    "StopPropagatedFrom",
+    "JoinsNamespaceOf",
+    "RequiresMountsFor",
+    "OnFailureJobMode",
+    "IgnoreOnIsolate",
+    "StopWhenUnneeded",
+    "RefuseManualStart",
+    "RefuseManualStop",
+    "AllowIsolate",
+    "DefaultDependencies",
+    "CollectMode",
+    "FailureAction",
+    "SuccessAction",
+    "FailureActionExitStatus",
+    "SuccessActionExitStatus",
+    "JobTimeoutSec",
+    "JobRunningTimeoutSec",
+    "JobTimeoutAction",
+    "JobTimeoutRebootArgument",
+    "StartLimitIntervalSec",
+    "StartLimitBurst",
+    "StartLimitAction",
+    "RebootArgument",
+    "SourcePath",
+    "ConditionArchitecture",
+    "ConditionFirmware",
+    "ConditionVirtualization",
+    "ConditionHost",
+    "ConditionKernelCommandLine",
+    "ConditionKernelVersion",
+    "ConditionEnvironment",
+    "ConditionSecurity",
+    "ConditionCapability",
+    "ConditionACPower",
+    "ConditionNeedsUpdate",
+    "ConditionFirstBoot",
+    "ConditionPathExists",
+    "ConditionPathExistsGlob",
+    "ConditionPathIsDirectory",
+    "ConditionPathIsSymbolicLink",
+    "ConditionPathIsMountPoint",
+    "ConditionPathIsReadWrite",
+    "ConditionPathIsEncrypted",
+    "ConditionDirectoryNotEmpty",
+    "ConditionFileNotEmpty",
+    "ConditionFileIsExecutable",
+    "ConditionUser",
+    "ConditionGroup",
+    "ConditionControlGroupController",
+    "ConditionMemory",
+    "ConditionCPUs",
+    "ConditionOSRelease",
+    "AssertArchitecture",
+    "AssertVirtualization",
+    "AssertHost",
+    "AssertKernelCommandLine",
+    "AssertEnvironment",
+    "AssertPathExists",
+    "AssertPathExistsGlob",
+    "AssertPathIsDirectory",
+    "AssertPathIsMountPoint",
+    "AssertPathIsReadWrite",
+    "AssertDirectoryNotEmpty",
+    "AssertFileNotEmpty",
+    "AssertFileIsExecutable",
+    "AssertUser",
+    "AssertGroup",
+    "ConditionCPUFeature",
+    "ConditionCredential",
+    "ConditionMemoryPressure",
+    "ConditionCPUPressure",
+    "ConditionIOPressure",
+    "AssertFirmware",
+    "AssertKernelVersion",
+    "AssertSecurity",
+    "AssertCapability",
+    "AssertACPower",
+    "AssertNeedsUpdate",
+    "AssertFirstBoot",
+    "AssertPathIsSymbolicLink",
+    "AssertPathIsEncrypted",
+    "AssertControlGroupController",
+    "AssertMemory",
+    "AssertCPUs",
+    "AssertCPUFeature",
+    "AssertOSRelease",
+    "AssertCredential",
+    "AssertMemoryPressure",
+    "AssertCPUPressure",
+    "AssertIOPressure",
+    "OnSuccessJobMode",
+    "SurviveFinalKillSignal",
+];
+
+const SERVICE: &[&str] = &[
+    "Type",
+    "ExitType",
+    "RemainAfterExit",
+    "GuessMainPID",
+    "PIDFile",
+    "BusName",
+    "ExecStart",
+    "ExecStartPre",
+    "ExecStartPost",
+    "ExecCondition",
+    "ExecReload",
+    "ExecStop",
+    "ExecStopPost",
+    "RestartSec",
+    "RestartSteps",
+    "RestartMaxDelaySec",
+    "TimeoutStartSec",
+    "TimeoutStopSec",
+    "TimeoutAbortSec",
+    "TimeoutSec",
+    "TimeoutStartFailureMode",
+    "TimeoutStopFailureMode",
+    "RuntimeMaxSec",
+    "RuntimeRandomizedExtraSec",
+    "WatchdogSec",
+    "Restart",
+    "RestartMode",
+    "SuccessExitStatus",
+    "RestartPreventExitStatus",
+    "RestartForceExitStatus",
+    "RootDirectoryStartOnly",
+    "NonBlocking",
+    "NotifyAccess",
+    "Sockets",
+    "FileDescriptorStoreMax",
+    "FileDescriptorStorePreserve",
+    "USBFunctionDescriptors",
+    "USBFunctionStrings",
+    "OOMPolicy",
+    "OpenFile",
+    "ReloadSignal",
+];
+
+/// Directives of systemd.exec(5), valid in [Service] and [Socket]
+const EXEC: &[&str] = &[
+    "ExecSearchPath",
+    "WorkingDirectory",
+    "RootDirectory",
+    "RootImage",
+    "RootImageOptions",
+    "RootEphemeral",
+    "RootHash",
+    "RootHashSignature",
+    "RootVerity",
+    "RootImagePolicy",
+    "MountImagePolicy",
+    "ExtensionImagePolicy",
+    "MountAPIVFS",
+    "ProtectProc",
+    "ProcSubset",
+    "BindPaths",
+    "BindReadOnlyPaths",
+    "MountImages",
+    "ExtensionImages",
+    "ExtensionDirectories",
+    "User",
+    "Group",
+    "DynamicUser",
+    "SupplementaryGroups",
+    "SetLoginEnvironment",
+    "PAMName",
+    "CapabilityBoundingSet",
+    "AmbientCapabilities",
+    "NoNewPrivileges",
+    "SecureBits",
+    "SELinuxContext",
+    "AppArmorProfile",
+    "SmackProcessLabel",
+    "LimitCPU",
+    "LimitFSIZE",
+    "LimitDATA",
+    "LimitSTACK",
+    "LimitCORE",
+    "LimitRSS",
+    "LimitNOFILE",
+    "LimitAS",
+    "LimitNPROC",
+    "LimitMEMLOCK",
+    "LimitLOCKS",
+    "LimitSIGPENDING",
+    "LimitMSGQUEUE",
+    "LimitNICE",
+    "LimitRTPRIO",
+    "LimitRTTIME",
+    "UMask",
+    "CoredumpFilter",
+    "KeyringMode",
+    "OOMScoreAdjust",
+    "TimerSlackNSec",
+    "Personality",
+    "IgnoreSIGPIPE",
+    "Nice",
+    "CPUSchedulingPolicy",
+    "CPUSchedulingPriority",
+    "CPUSchedulingResetOnFork",
+    "CPUAffinity",
+    "NUMAPolicy",
+    "NUMAMask",
+    "IOSchedulingClass",
+    "IOSchedulingPriority",
+    "ProtectSystem",
+    "ProtectHome",
+    "RuntimeDirectory",
+    "StateDirectory",
+    "CacheDirectory",
+    "LogsDirectory",
+    "ConfigurationDirectory",
+    "RuntimeDirectoryMode",
+    "StateDirectoryMode",
+    "CacheDirectoryMode",
+    "LogsDirectoryMode",
+    "ConfigurationDirectoryMode",
+    "RuntimeDirectoryPreserve",
+    "TimeoutCleanSec",
+    "ReadWritePaths",
+    "ReadOnlyPaths",
+    "InaccessiblePaths",
+    "ExecPaths",
+    "NoExecPaths",
+    "TemporaryFileSystem",
+    "PrivateTmp",
+    "PrivateDevices",
+    "PrivateNetwork",
+    "NetworkNamespacePath",
+    "PrivateIPC",
+    "IPCNamespacePath",
+    "MemoryKSM",
+    "PrivateUsers",
+    "ProtectHostname",
+    "ProtectClock",
+    "ProtectKernelTunables",
+    "ProtectKernelModules",
+    "ProtectKernelLogs",
+    "ProtectControlGroups",
+    "RestrictAddressFamilies",
+    "RestrictFileSystems",
+    "RestrictNamespaces",
+    "LockPersonality",
+    "MemoryDenyWriteExecute",
+    "RestrictRealtime",
+    "RestrictSUIDSGID",
+    "RemoveIPC",
+    "PrivateMounts",
+    "MountFlags",
+    "SystemCallFilter",
+    "SystemCallErrorNumber",
+    "SystemCallArchitectures",
+    "SystemCallLog",
+    "Environment",
+    "EnvironmentFile",
+    "PassEnvironment",
+    "UnsetEnvironment",
+    "StandardInput",
+    "StandardOutput",
+    "StandardError",
+    "StandardInputText",
+    "StandardInputData",
+    "LogLevelMax",
+    "LogExtraFields",
+    "LogRateLimitIntervalSec",
+    "LogRateLimitBurst",
+    "LogFilterPatterns",
+    "LogNamespace",
+    "SyslogIdentifier",
+    "SyslogFacility",
+    "SyslogLevel",
+    "SyslogLevelPrefix",
+    "TTYPath",
+    "TTYReset",
+    "TTYVHangup",
+    "TTYRows",
+    "TTYColumns",
+    "TTYVTDisallocate",
+    "LoadCredential",
+    "LoadCredentialEncrypted",
+    "ImportCredential",
+    "SetCredential",
+    "SetCredentialEncrypted",
+    "UtmpIdentifier",
+    "UtmpMode",
+];
+
+/// Directives of systemd.kill(5), valid in [Service] and [Socket]
+const KILL: &[&str] = &[
+    "KillMode",
+    "KillSignal",
+    "RestartKillSignal",
+    "SendSIGHUP",
+    "SendSIGKILL",
+    "FinalKillSignal",
+    "WatchdogSignal",
+];
+
+/// Directives of systemd.resource-control(5), valid in [Service] and
+/// [Socket]
+const RESOURCE_CONTROL: &[&str] = &[
+    "CPUAccounting",
+    "CPUWeight",
+    "StartupCPUWeight",
+    "CPUQuota",
+    "CPUQuotaPeriodSec",
+    "AllowedCPUs",
+    "StartupAllowedCPUs",
+    "AllowedMemoryNodes",
+    "StartupAllowedMemoryNodes",
+    "MemoryAccounting",
+    "MemoryMin",
+    "MemoryLow",
+    "StartupMemoryLow",
+    "DefaultStartupMemoryLow",
+    "MemoryHigh",
+    "StartupMemoryHigh",
+    "MemoryMax",
+    "StartupMemoryMax",
+    "MemorySwapMax",
+    "StartupMemorySwapMax",
+    "MemoryZSwapMax",
+    "StartupMemoryZSwapMax",
+    "MemoryZSwapWriteback",
+    "TasksAccounting",
+    "TasksMax",
+    "IOAccounting",
+    "IOWeight",
+    "StartupIOWeight",
+    "IODeviceWeight",
+    "IOReadBandwidthMax",
+    "IOWriteBandwidthMax",
+    "IOReadIOPSMax",
+    "IOWriteIOPSMax",
+    "IODeviceLatencyTargetSec",
+    "IPAccounting",
+    "IPAddressAllow",
+    "IPAddressDeny",
+    "SocketBindAllow",
+    "SocketBindDeny",
+    "RestrictNetworkInterfaces",
+    "NFTSet",
+    "IPIngressFilterPath",
+    "IPEgressFilterPath",
+    "BPFProgram",
+    "DeviceAllow",
+    "DevicePolicy",
+    "Slice",
+    "Delegate",
+    "DelegateSubgroup",
+    "DisableControllers",
+    "ManagedOOMSwap",
+    "ManagedOOMMemoryPressure",
+    "ManagedOOMMemoryPressureLimit",
+    "ManagedOOMPreference",
+    "MemoryPressureWatch",
+    "MemoryPressureThresholdSec",
+    "CoredumpReceive",
+    "CPUShares",
+    "StartupCPUShares",
+    "MemoryLimit",
+    "BlockIOAccounting",
+    "BlockIOWeight",
+    "StartupBlockIOWeight",
+    "BlockIODeviceWeight",
+    "BlockIOReadBandwidth",
+    "BlockIOWriteBandwidth",
+];
+
+const TIMER: &[&str] = &[
+    "OnActiveSec",
+    "OnBootSec",
+    "OnStartupSec",
+    "OnUnitActiveSec",
+    "OnUnitInactiveSec",
+    "OnCalendar",
+    "AccuracySec",
+    "RandomizedDelaySec",
+    "FixedRandomDelay",
+    "OnClockChange",
+    "OnTimezoneChange",
+    "Unit",
+    "Persistent",
+    "WakeSystem",
+    "RemainAfterElapse",
+];
+
+const PATH: &[&str] = &[
+    "PathExists",
+    "PathExistsGlob",
+    "PathChanged",
+    "PathModified",
+    "DirectoryNotEmpty",
+    "Unit",
+    "MakeDirectory",
+    "DirectoryMode",
+    "TriggerLimitIntervalSec",
+    "TriggerLimitBurst",
+];
+
+const SOCKET: &[&str] = &[
+    "ListenStream",
+    "ListenDatagram",
+    "ListenSequentialPacket",
+    "ListenFIFO",
+    "ListenSpecial",
+    "ListenNetlink",
+    "ListenMessageQueue",
+    "ListenUSBFunction",
+    "SocketProtocol",
+    "BindIPv6Only",
+    "Backlog",
+    "BindToDevice",
+    "SocketUser",
+    "SocketGroup",
+    "SocketMode",
+    "DirectoryMode",
+    "Accept",
+    "Writable",
+    "FlushPending",
+    "MaxConnections",
+    "MaxConnectionsPerSource",
+    "KeepAlive",
+    "KeepAliveTimeSec",
+    "KeepAliveIntervalSec",
+    "KeepAliveProbes",
+    "NoDelay",
+    "Priority",
+    "DeferAcceptSec",
+    "ReceiveBuffer",
+    "SendBuffer",
+    "IPTOS",
+    "IPTTL",
+    "Mark",
+    "ReusePort",
+    "FreeBind",
+    "Transparent",
+    "Broadcast",
+    "PassCredentials",
+    "PassSecurity",
+    "PassPacketInfo",
+    "Timestamping",
+    "TCPCongestion",
+    "SELinuxContextFromNet",
+    "SmackLabel",
+    "SmackLabelIPIn",
+    "SmackLabelIPOut",
+    "PipeSize",
+    "RemoveOnStop",
+    "Symlinks",
+    "FileDescriptorName",
+    "Service",
+    "TriggerLimitIntervalSec",
+    "TriggerLimitBurst",
+    "ExecStartPre",
+    "ExecStartPost",
+    "ExecStopPre",
+    "ExecStopPost",
+    "TimeoutSec",
+];
+
+const INSTALL: &[&str] = &[
+    "Alias",
+    "WantedBy",
+    "RequiredBy",
+    "UpheldBy",
+    "Also",
+    "DefaultInstance",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directive(s: &str) -> (UnitKind, Section, String, String) {
+        let d = Directive::from_str(s)
+            .unwrap_or_else(|e| panic!("{}: {}", s, e.0));
+        (d.kind, d.section, d.key, d.value)
+    }
+
+    #[test]
+    fn set_directives() {
+        assert_eq!(
+            directive("Service.Restart=on-failure"),
+            (
+                UnitKind::Service,
+                Section::Service,
+                String::from("Restart"),
+                String::from("on-failure")
+            )
+        );
+        assert_eq!(
+            directive("Unit.Description = Backup = daily"),
+            (
+                UnitKind::Service,
+                Section::Unit,
+                String::from("Description"),
+                String::from("Backup = daily")
+            )
+        );
+        assert_eq!(
+            directive("timer.Unit.Description=Daily backup").0,
+            UnitKind::Timer
+        );
+        assert_eq!(
+            directive("Timer.OnCalendar=daily").0,
+            UnitKind::Timer
+        );
+        assert_eq!(
+            directive("service.Install.WantedBy=default.target").1,
+            Section::Install
+        );
+    }
+
+    #[test]
+    fn set_errors() {
+        for (s, error) in [
+            ("Service.Restart", "missing a value"),
+            ("Restart=always", "missing a section"),
+            ("Servce.Restart=always", "did you mean `Service`?"),
+            (
+                "service.Timer.OnCalendar=daily",
+                "has no [Timer] section",
+            ),
+            ("tiemr.Unit.Description=x", "tiemr"),
+        ] {
+            match Directive::from_str(s) {
+                Ok(_) => panic!("{} was accepted", s),
+                Err(e) => {
+                    assert!(e.0.contains(error), "{}: {}", s, e.0)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_keys_are_left_to_verify() {
+        // Newer or rarer directives must still get through --set
+        assert_eq!(
+            directive("Service.ProtectClock=yes").2,
+            "ProtectClock"
+        );
+        assert_eq!(directive("Service.Frobnicate=1").2, "Frobnicate");
+        assert!(Section::Service.check_key("ProtectClock").is_ok());
+        assert!(Section::Service.check_key("X-Backup").is_ok());
+        assert!(Section::Service.check_key("Frobnicate").is_err());
+    }
+
+    #[test]
+    fn check_key_suggestions() {
+        let error = |section: Section, key| {
+            section.check_key(key).unwrap_err().0
+        };
+        assert!(error(Section::Service, "Restat")
+            .ends_with("did you mean `Restart`?"));
+        assert_eq!(
+            error(Section::Service, "OnCalendar"),
+            "`OnCalendar` belongs in the [Timer] section, not [Service]"
+        );
+        assert_eq!(
+            error(Section::Timer, "Zzzzzz"),
+            "`Zzzzzz` is not a known directive of the [Timer] section"
+        );
+    }
+
+    #[test]
+    fn levenshtein_suggestions() {
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("Restart", "restart"), 0);
+        assert_eq!(distance("", "abc"), 3);
+        let candidates = || {
+            ["After", "Before", "Wants"].iter().map(|c| c.to_string())
+        };
+        assert_eq!(
+            suggestion("Aftr", candidates()),
+            ", did you mean `After`?"
+        );
+        assert_eq!(suggestion("Conflicts", candidates()), "");
+    }
+
+    #[test]
+    fn listen_addresses() {
+        let address = |s: &str| {
+            ListenAddress::from_str(s)
+                .unwrap_or_else(|e| panic!("{}: {}", s, e.0))
+        };
+        assert_eq!(address("8080"), ListenAddress::Port(8080));
+        assert_eq!(
+            address("127.0.0.1:80"),
+            ListenAddress::Inet("127.0.0.1:80".parse().unwrap())
+        );
+        assert_eq!(address("[::1]:443").port(), Some(443));
+        assert_eq!(address("[::1]:443").to_string(), "[::1]:443");
+        assert_eq!(
+            address("/run/app.sock"),
+            ListenAddress::Unix(PathBuf::from("/run/app.sock"))
+        );
+        assert_eq!(address("@app").to_string(), "@app");
+        assert_eq!(address("65535").port(), Some(65535));
+    }
+
+    #[test]
+    fn listen_address_errors() {
+        for (s, error) in [
+            ("0", "Port 0"),
+            ("127.0.0.1:0", "Port 0"),
+            ("65536", "use 1-65535"),
+            ("99999999999", "use 1-65535"),
+            ("run/app.sock", "absolute socket path"),
+            ("localhost:80", "not an IP address"),
+            ("", "absolute socket path"),
+        ] {
+            match ListenAddress::from_str(s) {
+                Ok(_) => panic!("`{}` was accepted", s),
+                Err(e) => {
+                    assert!(e.0.contains(error), "{}: {}", s, e.0)
+                }
+            }
+        }
+    }
+}
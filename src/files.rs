@@ -0,0 +1,220 @@
+use std::fs::{self, File};
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+
+/// The directory next to the unit files keeping the files autod replaced
+const BACKUP_DIR: &str = ".autod-backup";
+
+fn file_name(file: &Path) -> String {
+    file.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// Where the content of `file` replaced at `stamp` is kept
+fn backup_path(file: &Path, stamp: &str) -> PathBuf {
+    file.with_file_name(BACKUP_DIR).join(format!(
+        "{}.{}",
+        file_name(file),
+        stamp
+    ))
+}
+
+/// Like `backup_path`, creating the backup directory
+fn backup_file(file: &Path, stamp: &str) -> io::Result<PathBuf> {
+    fs::create_dir_all(file.with_file_name(BACKUP_DIR))?;
+    Ok(backup_path(file, stamp))
+}
+
+/// The current time in microseconds, which none of `files` has a backup
+/// of yet, so backups of one write share it and never replace others
+fn stamp<'a>(
+    files: impl Iterator<Item = &'a Path> + Clone,
+) -> String {
+    loop {
+        let stamp =
+            Local::now().format("%Y%m%dT%H%M%S%6f").to_string();
+        if files
+            .clone()
+            .all(|file| !backup_path(file, &stamp).exists())
+        {
+            return stamp;
+        }
+    }
+}
+
+fn write_synced(file: &Path, content: &str) -> io::Result<()> {
+    let mut handle = File::create(file)?;
+    handle.write_all(content.as_bytes())?;
+    handle.sync_all()
+}
+
+/// Writes all files or none of them
+///
+/// The contents go to temporary files next to the files first, which then
+/// replace them, if a replacement fails the files already replaced are
+/// rolled back. Replaced content is kept with a timestamp in the backup
+/// directory, the backups are returned.
+pub fn write_all(
+    files: &[(&Path, String)],
+) -> io::Result<Vec<PathBuf>> {
+    let mut old = Vec::new();
+    for (file, _) in files {
+        old.push(match fs::read_to_string(file) {
+            Ok(content) => Some(content),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        });
+    }
+
+    let mut temps = Vec::new();
+    let mut backups = Vec::new();
+    let prepared: io::Result<()> = (|| {
+        for (file, content) in files {
+            let temp = file.with_file_name(format!(
+                ".{}.autod",
+                file_name(file)
+            ));
+            temps.push(temp.clone());
+            write_synced(&temp, content)?;
+        }
+        let stamp = stamp(files.iter().map(|(file, _)| *file));
+        for ((file, content), old) in files.iter().zip(&old) {
+            match old {
+                Some(old) if old != content => {
+                    let backup = backup_file(file, &stamp)?;
+                    write_synced(&backup, old)?;
+                    backups.push(backup);
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    })();
+    if let Err(e) = prepared {
+        for temp in &temps {
+            let _ = fs::remove_file(temp);
+        }
+        return Err(e);
+    }
+
+    for (i, ((file, _), temp)) in files.iter().zip(&temps).enumerate()
+    {
+        if let Err(e) = fs::rename(temp, file) {
+            for ((file, _), old) in files[..i].iter().zip(&old) {
+                let _ = match old {
+                    Some(old) => fs::write(file, old),
+                    None => fs::remove_file(file),
+                };
+            }
+            for temp in &temps[i..] {
+                let _ = fs::remove_file(temp);
+            }
+            return Err(e);
+        }
+    }
+    Ok(backups)
+}
+
+/// Removes files by moving them into the backup directory, so `restore`
+/// can bring them back, the backups are returned
+pub fn remove_all(files: &[&Path]) -> io::Result<Vec<PathBuf>> {
+    let stamp = stamp(files.iter().copied());
+    let mut backups = Vec::new();
+    for file in files {
+        let backup = backup_file(file, &stamp)?;
+        fs::rename(file, &backup)?;
+        backups.push(backup);
+    }
+    Ok(backups)
+}
+
+/// Restores the unit files of `name` in `dir` from their latest backup
+///
+/// The current files are backed up in turn, so restoring again undoes
+/// the restore. Returns the restored files.
+pub fn restore(
+    dir: &Path,
+    name: &str,
+) -> Result<Vec<PathBuf>, String> {
+    let backup_dir = dir.join(BACKUP_DIR);
+    let entries = fs::read_dir(&backup_dir).map_err(|e| {
+        format!(
+            "There are no backups in {}: {}",
+            backup_dir.display(),
+            e
+        )
+    })?;
+
+    // `backup.timer.20261019T093000123456` was `backup.timer`
+    let mut backups: Vec<(String, PathBuf, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter_map(|backup| {
+            let backup_name = file_name(&backup);
+            let (unit, stamp) = backup_name.rsplit_once('.')?;
+            let (unit_name, _) = unit.rsplit_once('.')?;
+            if unit_name.trim_end_matches('@') != name {
+                return None;
+            }
+            Some((stamp.to_owned(), backup.clone(), dir.join(unit)))
+        })
+        .collect();
+    let latest = backups
+        .iter()
+        .map(|(stamp, _, _)| stamp.clone())
+        .max()
+        .ok_or_else(|| {
+            format!(
+                "There is no backup of {} in {}",
+                name,
+                backup_dir.display()
+            )
+        })?;
+    backups.retain(|(stamp, _, _)| *stamp == latest);
+
+    let mut files = Vec::new();
+    for (_, backup, file) in &backups {
+        let content = fs::read_to_string(backup).map_err(|e| {
+            format!("Unable to read {}: {}", backup.display(), e)
+        })?;
+        files.push((file.as_path(), content));
+    }
+    write_all(&files).map_err(|e| {
+        format!(
+            "Unable to restore {}, nothing was changed: {}",
+            name, e
+        )
+    })?;
+    for (_, backup, _) in &backups {
+        let _ = fs::remove_file(backup);
+    }
+    Ok(backups.into_iter().map(|(_, _, file)| file).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backups_in_the_same_second_are_kept() {
+        let dir = std::env::temp_dir()
+            .join(format!("autod-files-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("backup.service");
+        let mut backups = Vec::new();
+        for content in ["first", "second", "third"] {
+            backups.extend(
+                write_all(&[(file.as_path(), content.to_owned())])
+                    .unwrap(),
+            );
+        }
+        let kept: Vec<String> = backups
+            .iter()
+            .map(|backup| fs::read_to_string(backup).unwrap())
+            .collect();
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(kept, ["first", "second"]);
+    }
+}
@@ -1,24 +1,126 @@
-#![feature(non_ascii_idents)]
 #![allow(uncommon_codepoints, dead_code)]
 use clap::Clap;
-use dirs;
-use indoc::{formatdoc, printdoc};
+use indoc::printdoc;
+use std::collections::HashSet;
+use std::panic;
+use std::path::{Component, Path, PathBuf};
 use std::process::Command;
+use std::str::{self, FromStr};
 use std::{env, fs};
-use std::{path::PathBuf, str};
 use unwrap::unwrap;
 
 mod cli;
+use cli::calendar::DstPolicy;
+use cli::directive::{
+    ListenAddress, PathTrigger, SocketKind, UnitKind,
+};
 use cli::{Opt, Target};
 
-// TODO This should be solved better
-use crate::cli::calendar::TimerAble;
+mod unit;
+use unit::UnitFile;
+
+mod verify;
+
+mod diff;
+
+mod files;
+
+mod config;
+use config::Config;
 
 // FIXME Is this the right file to hold this?
 impl Target {
-    fn timer(&self) -> Option<String> {
+    /// The unit triggering the service, if it is not started by a target
+    fn companion(
+        &self,
+        service_name: &str,
+        dst: DstPolicy,
+    ) -> Option<(UnitKind, UnitFile)> {
+        let mut file = UnitFile::new();
+        match self {
+            Target::When { timer } => {
+                file.comment(format!("Runs {}", timer.explain()))
+                    .add(
+                        "Unit",
+                        "Description",
+                        format!("Runs {} on a timer", service_name),
+                    )
+                    .add_lines(
+                        "Timer",
+                        &timer
+                            .timer_with(dst)
+                            .unwrap_or_else(|e| panic!("{}", e)),
+                    )
+                    .add("Install", "WantedBy", "timers.target");
+                Some((UnitKind::Timer, file))
+            }
+            Target::Watch { path, trigger } => {
+                let path = watched_path(path, *trigger);
+                file.add(
+                    "Unit",
+                    "Description",
+                    format!(
+                        "Runs {} when {} changes",
+                        service_name,
+                        path.display()
+                    ),
+                )
+                .add(
+                    "Path",
+                    &trigger.to_string(),
+                    path.to_string_lossy(),
+                )
+                .add(
+                    "Install",
+                    "WantedBy",
+                    "paths.target",
+                );
+                Some((UnitKind::Path, file))
+            }
+            Target::Listen {
+                address,
+                kind,
+                accept,
+            } => {
+                file.add(
+                    "Unit",
+                    "Description",
+                    format!("Socket for {}", service_name),
+                )
+                .add(
+                    "Socket",
+                    &kind.to_string(),
+                    listen_address(address, *kind),
+                );
+                if *accept {
+                    file.add("Socket", "Accept", "yes");
+                }
+                file.add("Install", "WantedBy", "sockets.target");
+                Some((UnitKind::Socket, file))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the service is a template, started once per instance
+    fn template(&self) -> bool {
+        matches!(self, Target::Listen { accept: true, .. })
+    }
+
+    fn service(&self) -> Option<String> {
         match self {
-            Target::When { timer } => Some(timer.timer()),
+            Target::Listen { accept: true, .. } => {
+                Some(String::from("StandardInput=socket"))
+            }
+            _ => None,
+        }
+    }
+
+    fn unit(&self) -> Option<String> {
+        match self {
+            Target::After { unit } => {
+                Some(format!("After={0}\nWants={0}", unit))
+            }
             _ => None,
         }
     }
@@ -26,7 +128,10 @@ impl Target {
     fn install(&self) -> Option<String> {
         match self {
             Target::On { event } => {
-                Some(String::from("WantedBy=") + event)
+                Some(format!("WantedBy={}", event))
+            }
+            Target::After { unit: _ } => {
+                Some(String::from("WantedBy=default.target"))
             }
             _ => None,
         }
@@ -35,13 +140,189 @@ impl Target {
 
 fn main() {
     let opt = Opt::parse();
-    println!("{:?}", opt);
-    let prog = opt.command;
+    if opt.explain {
+        match &opt.target {
+            Some(Target::When { timer }) => {
+                println!("Runs {}", timer.explain());
+                return;
+            }
+            _ => panic!(
+                "--explain needs a timer, use `when` to create one"
+            ),
+        }
+    }
+    if let Some(Target::Verify { files }) = &opt.target {
+        verify_files(files);
+        return;
+    }
+    if let Some(Target::Restore { name }) = &opt.target {
+        let dir =
+            opt.output_dir.clone().unwrap_or_else(default_output_dir);
+        for file in files::restore(&dir, name)
+            .unwrap_or_else(|e| panic!("{}", e))
+        {
+            println!("Restored {}", file.display());
+        }
+        return;
+    }
+    if let Some(Target::Apply { file }) = &opt.target {
+        apply(file, &opt);
+        return;
+    }
+    let units = generate(&opt);
+
+    if opt.diff || opt.check_only {
+        let mut changed = false;
+        for (_, file, unit) in units.files() {
+            let current = fs::read_to_string(file).ok();
+            let content = unit.to_string();
+            if current.as_deref() == Some(content.as_str()) {
+                continue;
+            }
+            changed = true;
+            if opt.diff {
+                print_diff(file, current.as_deref(), Some(&content));
+            } else if current.is_some() {
+                println!("{} is out of date", file.display());
+            } else {
+                println!("{} does not exist", file.display());
+            }
+        }
+        if opt.check_only && changed {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    if !opt.print {
+        for (kind, file, _) in units.files() {
+            if !opt.overwrite && file.exists() {
+                panic!(
+                    "The {} file {} already exists, consider using -o to \
+                    overwrite, -p to print, or -n to provide a different \
+                    name",
+                    kind,
+                    file.display()
+                )
+            }
+            if opt.overwrite && file.is_dir() {
+                panic!(
+                    "The {} file {} is a directory, consider moving it or \
+                    using -p to print or -n to provide a different name",
+                    kind,
+                    file.display()
+                )
+            }
+        }
+    }
+
+    if opt.print {
+        if units.companion.is_some() {
+            printdoc!(
+                "
+                Service File:
+                ===
+                {}
+                ===
+
+                ",
+                units.service
+            );
+        } else {
+            println!("{}", units.service);
+        }
+        if let Some((kind, _, companion)) = &units.companion {
+            printdoc!(
+                "
+                {} File:
+                ===
+                {}
+                ===
+                ",
+                kind.section(),
+                companion
+            );
+        }
+    } else {
+        create_output_dir(units.service_file.parent().unwrap());
+        // Written together, so a service never ends up without its timer
+        let files: Vec<(&Path, String)> = units
+            .files()
+            .map(|(_, file, unit)| (file, unit.to_string()))
+            .collect();
+        let backups = files::write_all(&files).unwrap_or_else(|e| {
+            panic!("Unable to write the unit files, nothing was changed: {}", e)
+        });
+        for backup in backups {
+            println!(
+                "Kept the replaced file as {}, use `autod restore {}` to \
+                bring it back",
+                backup.display(),
+                units.name
+            );
+        }
+    }
+
+    if !opt.instance.is_empty() {
+        let (file, unit) = units.instance_file();
+        enable_instances(file, unit, &opt.instance, opt.print);
+    }
+
+    if opt.check && !opt.print {
+        let files: Vec<&Path> =
+            units.files().map(|(_, file, _)| file).collect();
+        analyze_files(&opt.systemd_analyze, &files);
+    }
+}
+
+/// The unit files of a service
+struct Units {
+    /// The name of the service, as `autod restore` takes it
+    name: String,
+    service_file: PathBuf,
+    service: UnitFile,
+    /// The unit triggering the service, with its file
+    companion: Option<(UnitKind, PathBuf, UnitFile)>,
+}
+
+impl Units {
+    /// The files with their kind, the service first
+    fn files(
+        &self,
+    ) -> impl Iterator<Item = (UnitKind, &Path, &UnitFile)> {
+        std::iter::once((
+            UnitKind::Service,
+            self.service_file.as_path(),
+            &self.service,
+        ))
+        .chain(
+            self.companion.iter().map(|(kind, file, unit)| {
+                (*kind, file.as_path(), unit)
+            }),
+        )
+    }
+
+    /// The file instances are enabled through, the companion if there is
+    /// one
+    fn instance_file(&self) -> (&Path, &UnitFile) {
+        match &self.companion {
+            Some((_, file, unit)) => (file, unit),
+            None => (&self.service_file, &self.service),
+        }
+    }
+}
+
+/// Creates the unit files for a command line, panicking on mistakes
+fn generate(opt: &Opt) -> Units {
+    let timer_options = timer_options(opt);
+    let prog = opt.command.clone().unwrap_or_else(|| {
+        panic!("autod needs a command to run, e.g. `autod backup.sh when daily`")
+    });
     let (progpath, progname) = match 1 {
         _ if prog.is_absolute() => (prog, None),
         _ if prog
             .parent()
-            .map_or(true, |p| p.as_os_str().is_empty()) =>
+            .is_none_or(|p| p.as_os_str().is_empty()) =>
         {
             if prog.is_file() {
                 eprintln!(
@@ -89,32 +370,40 @@ fn main() {
         }
     };
 
-    let target = opt.target.unwrap_or_default();
-    let custom_output_dir = opt.output_dir.is_some();
-    let mut service_file = opt.output_dir.unwrap_or_else(|| {
-        let mut file = dirs::config_dir()
-            .expect("Could not find the config Directory");
-        file.push("systemd/user");
-        file
+    let no_target = Target::No;
+    let target = opt.target.as_ref().unwrap_or(&no_target);
+    let mut service_file =
+        opt.output_dir.clone().unwrap_or_else(default_output_dir);
+
+    let name = opt.name.clone().unwrap_or_else(|| {
+        progname.unwrap_or_else(|| {
+            progpath.to_string_lossy().as_ref().to_owned()
+        })
     });
-    if !service_file.is_dir() {
-        fs::create_dir_all(&service_file).expect(
-            "Could not find or create the systemd config folder.",
-        );
-        if !custom_output_dir {
-            println!(
-                "Created {}, make sure you have systemd installed.",
-                service_file.display()
-            );
-        }
+    let name = match name.strip_suffix('@') {
+        Some(name) if opt.template => name.to_owned(),
+        _ => name,
+    };
+    if name.contains('@') {
+        panic!(
+            "The name {} contains @, which separates template and instance \
+            in systemd, use -t to create a template or -n to provide a \
+            different name",
+            name
+        )
+    }
+    if !opt.template && !opt.instance.is_empty() {
+        panic!("Only templates have instances, use -t to create one")
+    }
+    if target.template() && !opt.instance.is_empty() {
+        panic!(
+            "Instances of a socket with --accept are created per \
+            connection and can not be enabled"
+        )
     }
 
     let service_name = sanitize_filename::sanitize_with_options(
-        opt.name.unwrap_or_else(|| {
-            progname.unwrap_or_else(|| {
-                progpath.to_string_lossy().as_ref().to_owned()
-            })
-        }),
+        name,
         sanitize_filename::Options {
             windows: opt.windows,
             truncate: false,
@@ -125,115 +414,869 @@ fn main() {
     service_file.push(&service_name);
     service_file.set_extension("service");
 
-    let timer_file = service_file.with_extension("timer");
-
-    if !opt.print {
-        match (&target, opt.overwrite) {
-            (_, false) if service_file.exists() => {
-                panic!(
-                "The service file {} already exists, consider using -o to \
-                overwrite, -p to print, or -n to provide a different name",
-                service_file.display()
+    let mut args = opt.args.clone();
+    if opt.template
+        && !args.iter().any(|a| {
+            a.contains("%i") || a.contains("%I") || a.contains("%f")
+        })
+    {
+        // Paths escaped with `systemd-escape --path` need `%f` to get the
+        // leading slash back, which every other instance would get too
+        let paths = opt
+            .instance
+            .iter()
+            .filter(|i| i.starts_with('/'))
+            .count();
+        if paths > 0 && paths < opt.instance.len() {
+            panic!(
+                "The instances mix paths and names, which get passed \
+                differently, create a template for each or use %I or %f \
+                in --arg"
             )
+        }
+        args.push(String::from(if paths > 0 { "%f" } else { "%I" }));
+    }
+    let exec_start = std::iter::once(progpath.to_str().unwrap())
+        .chain(args.iter().map(String::as_str))
+        .map(unit::quote)
+        .collect::<Vec<String>>()
+        .join(" ");
+
+    let mut service = UnitFile::new();
+    service
+        .add(
+            "Unit",
+            "Description",
+            if opt.template {
+                format!(
+                    "Runs {} for %I, created by autod",
+                    service_name
+                )
+            } else {
+                format!("Runs {}, created by autod", service_name)
+            },
+        )
+        .add("Service", "ExecStart", exec_start);
+
+    if let Some(lines) = target.service() {
+        service.add_lines("Service", &lines);
+    }
+    if let Some(unit) = target.unit() {
+        service.add_lines("Unit", &unit);
+    }
+    for (key, units) in &[
+        ("After", &opt.after),
+        ("Requires", &opt.requires),
+        ("Wants", &opt.wants),
+        ("BindsTo", &opt.binds_to),
+        ("PartOf", &opt.part_of),
+    ] {
+        for unit in units.iter() {
+            service.add("Unit", key, unit.to_string());
+        }
+    }
+
+    if let Some(install) = target.install() {
+        service.add_lines("Install", &install);
+    }
+
+    if let Target::When { timer } = target {
+        if !opt.allow_past {
+            timer.check_future().unwrap_or_else(|e| panic!("{}", e));
+        }
+        for warning in timer.warnings() {
+            eprintln!("{}", warning);
+        }
+    }
+
+    let mut companion = target.companion(&service_name, opt.dst);
+
+    match &mut companion {
+        Some((UnitKind::Timer, file)) => {
+            for (key, value) in timer_options {
+                file.add("Timer", key, value);
             }
-            (_, true) if service_file.is_dir() => {
-                panic!(
-                "The service file {} is a directory, consider moving it or \
-                using -p to print or -n to provide a different name",
-                service_file.display()
-            )
+        }
+        _ if !timer_options.is_empty() => panic!(
+            "{} only {} to timers, use `when` to create one",
+            timer_options
+                .iter()
+                .map(|(key, _)| *key)
+                .collect::<Vec<&str>>()
+                .join(", "),
+            if timer_options.len() == 1 {
+                "applies"
+            } else {
+                "apply"
             }
-            (Target::When { timer: _ }, false)
-                if timer_file.exists() =>
+        ),
+        _ => {}
+    }
+
+    let mut replaced = HashSet::new();
+    for directive in &opt.set {
+        let file = match (directive.kind, &mut companion) {
+            (UnitKind::Service, _) => &mut service,
+            (kind, Some((companion_kind, file)))
+                if kind == *companion_kind =>
             {
-                panic!(
-                "The timer file {} already exists, consider using -o to \
-                overwrite, -p to print, or -n to provide a different name",
-                timer_file.display()
-            )
+                file
             }
-            (Target::When { timer: _ }, true)
-                if timer_file.is_dir() =>
-            {
-                panic!(
-                "The timer file {} is a directory, consider moving it or \
-                using -p to print or -n to provide a different name",
-                timer_file.display()
-            )
+            (kind, _) => panic!(
+                "Cannot set {}.{}, there is no {} file",
+                directive.section, directive.key, kind
+            ),
+        };
+        let section = directive.section.to_string();
+        if replaced.insert((
+            directive.kind,
+            section.clone(),
+            directive.key.clone(),
+        )) {
+            file.set(&section, &directive.key, &directive.value);
+        } else {
+            file.add(&section, &directive.key, &directive.value);
+        }
+    }
+
+    for (kind, file) in std::iter::once((UnitKind::Service, &service))
+        .chain(companion.as_ref().map(|(kind, file)| (*kind, file)))
+    {
+        let report = verify::verify(file, kind);
+        for problem in report.errors.iter().chain(&report.warnings) {
+            eprintln!(
+                "Warning, the generated {} file: {}",
+                kind, problem
+            );
+        }
+    }
+
+    if opt.check {
+        if let Some((UnitKind::Timer, timer)) = &companion {
+            for value in timer.get("Timer", "OnCalendar") {
+                match verify::analyze_calendar(
+                    &opt.systemd_analyze,
+                    value,
+                ) {
+                    Ok(Some(mismatch)) => eprintln!("{}", mismatch),
+                    Ok(None) => {}
+                    Err(e) => {
+                        eprintln!("{}", e);
+                        std::process::exit(1);
+                    }
+                }
             }
-            _ => {}
         }
     }
 
-    let mut service = formatdoc!(
-        "[Unit]
-        Description=Runs {}, created by autod
+    if opt.template {
+        service_file
+            .set_file_name(format!("{}@.service", service_name));
+    }
+    let companion_file = companion.as_ref().map(|(kind, _)| {
+        service_file.with_extension(kind.to_string())
+    });
+    if target.template() {
+        service_file
+            .set_file_name(format!("{}@.service", service_name));
+    }
 
-        [Service]
-        ExecStart={}
-        ",
-        service_name,
-        progpath.to_str().unwrap()
-    );
+    Units {
+        name: service_name,
+        service_file,
+        service,
+        companion: companion
+            .zip(companion_file)
+            .map(|((kind, unit), file)| (kind, file, unit)),
+    }
+}
 
-    if let Some(install) = target.install() {
-        service = formatdoc!(
-            "{}[Install]
-            {}
-            ",
-            service,
-            install
+/// Prints how `file` changes from `current` to `content`, `None` meaning
+/// it doesn't exist
+fn print_diff(
+    file: &Path,
+    current: Option<&str>,
+    content: Option<&str>,
+) {
+    let name = file.display().to_string();
+    print!(
+        "{}",
+        diff::unified(
+            current.unwrap_or_default(),
+            content.unwrap_or_default(),
+            if current.is_some() {
+                &name
+            } else {
+                "/dev/null"
+            },
+            if content.is_some() {
+                &name
+            } else {
+                "/dev/null"
+            },
         )
+    );
+}
+
+/// Runs `systemd-analyze verify` on written files, printing its complaints
+/// and exiting with 1 if it can't be run
+fn analyze_files(systemd_analyze: &Path, files: &[&Path]) {
+    match verify::analyze_files(systemd_analyze, files) {
+        Ok(Some(complaints)) => {
+            eprintln!(
+                "systemd-analyze verify reports:\n{}",
+                complaints
+            )
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Creates, updates and removes unit files to match the jobs of a config
+/// file, printing the plan first
+fn apply(config_file: &Path, opt: &Opt) {
+    let config_file =
+        fs::canonicalize(config_file).unwrap_or_else(|e| {
+            panic!("Unable to find {}: {}", config_file.display(), e)
+        });
+    let content =
+        fs::read_to_string(&config_file).unwrap_or_else(|e| {
+            panic!("Unable to read {}: {}", config_file.display(), e)
+        });
+    let config = Config::from_str(&content).unwrap_or_else(|e| {
+        panic!("{}: {}", config_file.display(), e)
+    });
+    let command_lines = config.command_lines().unwrap_or_else(|e| {
+        panic!("{}: {}", config_file.display(), e)
+    });
+
+    // Relative paths of the jobs are relative to the config file, unlike
+    // the ones given to `autod apply`
+    let output_dir = opt.output_dir.as_ref().map(|dir| {
+        env::current_dir()
+            .map(|cwd| cwd.join(dir))
+            .unwrap_or_else(|_| dir.clone())
+    });
+    env::set_current_dir(config_file.parent().unwrap())
+        .unwrap_or_else(|e| {
+            panic!(
+                "Unable to change into the directory of {}: {}",
+                config_file.display(),
+                e
+            )
+        });
+
+    let marker = format!(
+        "Managed by autod apply from {}, change that file instead",
+        config_file.display()
+    );
+    let mut jobs = Vec::new();
+    for (name, mut line) in command_lines {
+        if let Some(dir) = &output_dir {
+            if !line
+                .iter()
+                .any(|arg| arg.starts_with("--output-dir="))
+            {
+                line.push(format!("--output-dir={}", dir.display()));
+            }
+        }
+        if opt.check {
+            line.push(String::from("--check"));
+            line.push(format!(
+                "--systemd-analyze={}",
+                opt.systemd_analyze.display()
+            ));
+        }
+        let job = Opt::try_parse_from(&line).unwrap_or_else(|e| {
+            panic!("{}: [{}] {}", config_file.display(), name, e)
+        });
+        let mut units = panic::catch_unwind(|| generate(&job))
+            .unwrap_or_else(|e| {
+                eprintln!(
+                    "in the job {} of {}",
+                    name,
+                    config_file.display()
+                );
+                panic::resume_unwind(e)
+            });
+        units.service.comment(marker.as_str());
+        if let Some((_, _, companion)) = &mut units.companion {
+            companion.comment(marker.as_str());
+        }
+        jobs.push((job, units));
     }
 
-    if opt.print {
-        if let Target::When { timer: _ } = target {
-            printdoc!(
-                "
-                Service File:
-                ===
-                {}
-                ===
+    // The jobs removed from the config are found by the marker in the
+    // directories autod apply writes to
+    let marker = format!("# {}", marker);
+    let managed =
+        |content: &str| content.lines().any(|line| line == marker);
+    let base = config_file.parent().unwrap();
+    let mut dirs: Vec<PathBuf> = vec![base.join(
+        config
+            .defaults
+            .iter()
+            .find(|(key, _)| key.replace('_', "-") == "output-dir")
+            .map(|(_, dir)| PathBuf::from(dir.to_string()))
+            .or(output_dir)
+            .unwrap_or_else(default_output_dir),
+    )];
+    let mut plan = Vec::new();
+    for (_, units) in &jobs {
+        for (_, file, unit) in units.files() {
+            let current = fs::read_to_string(file).ok();
+            let content = unit.to_string();
+            let action = match &current {
+                Some(current) if *current == content => continue,
+                Some(current) if managed(current) => "update",
+                Some(_) => "replace",
+                None => "create",
+            };
+            plan.push((
+                action,
+                base.join(file),
+                current,
+                Some(content),
+            ));
+        }
+        if let Some(dir) = units.service_file.parent() {
+            let dir = base.join(dir);
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+    }
+    for dir in &dirs {
+        let mut files: Vec<PathBuf> = match fs::read_dir(dir) {
+            Ok(entries) => entries
+                .filter_map(|entry| {
+                    entry.ok().map(|entry| entry.path())
+                })
+                .collect(),
+            Err(_) => continue,
+        };
+        files.sort();
+        for file in files {
+            let is_unit = file
+                .extension()
+                .and_then(|e| e.to_str())
+                .is_some_and(|e| UnitKind::from_str(e).is_ok());
+            let generated = jobs.iter().any(|(_, units)| {
+                units.files().any(|(_, generated, _)| {
+                    base.join(generated) == file
+                })
+            });
+            if !is_unit || generated || !file.is_file() {
+                continue;
+            }
+            match fs::read_to_string(&file) {
+                Ok(current) if managed(&current) => {
+                    plan.push(("remove", file, Some(current), None))
+                }
+                _ => {}
+            }
+        }
+    }
 
-                ",
-                service
-            );
-        } else {
-            println!("{}", service);
+    if plan.is_empty() {
+        println!(
+            "The units are up to date with {}",
+            config_file.display()
+        );
+    } else {
+        println!("Plan for {}:", config_file.display());
+        for (action, file, _, _) in &plan {
+            println!("  {:<7} {}", action, file.display());
+        }
+    }
+    if opt.diff {
+        for (_, file, current, content) in &plan {
+            print_diff(file, current.as_deref(), content.as_deref());
+        }
+    }
+    if opt.check_only && !plan.is_empty() {
+        std::process::exit(1);
+    }
+    if opt.diff || opt.check_only {
+        return;
+    }
+    if !opt.overwrite {
+        if let Some((_, file, _, _)) =
+            plan.iter().find(|(action, _, _, _)| *action == "replace")
+        {
+            panic!(
+                "{} was not written by autod apply, use -o to replace it \
+                or rename the job, nothing was changed",
+                file.display()
+            )
+        }
+    }
+
+    let written: Vec<(&Path, String)> = plan
+        .iter()
+        .filter_map(|(_, file, _, content)| {
+            content.clone().map(|content| (file.as_path(), content))
+        })
+        .collect();
+    for (file, _) in &written {
+        create_output_dir(file.parent().unwrap());
+    }
+    let mut backups = files::write_all(&written).unwrap_or_else(|e| {
+        panic!("Unable to write the unit files, nothing was changed: {}", e)
+    });
+    let removed: Vec<&Path> = plan
+        .iter()
+        .filter(|(_, _, _, content)| content.is_none())
+        .map(|(_, file, _, _)| file.as_path())
+        .collect();
+    backups.extend(files::remove_all(&removed).unwrap_or_else(|e| {
+        panic!("Unable to remove the units of dropped jobs: {}", e)
+    }));
+    for file in &removed {
+        disable(file);
+    }
+    for backup in backups {
+        // `backup@.service.20261019T093000123456` belongs to `backup`
+        let name = backup.file_name().unwrap().to_string_lossy();
+        let name = name
+            .rsplitn(3, '.')
+            .nth(2)
+            .unwrap_or_default()
+            .trim_end_matches('@');
+        println!(
+            "Kept the old file as {}, use `autod restore {}` to bring it \
+            back",
+            backup.display(),
+            name
+        );
+    }
+
+    for (job, units) in &jobs {
+        if !job.instance.is_empty() {
+            let (file, unit) = units.instance_file();
+            enable_instances(file, unit, &job.instance, false);
         }
+    }
+    if opt.check && !written.is_empty() {
+        let files: Vec<&Path> =
+            written.iter().map(|(file, _)| *file).collect();
+        analyze_files(&opt.systemd_analyze, &files);
+    }
+}
+
+/// Removes the links `enable_instances` or `systemctl enable` left in the
+/// `.wants` directories for a removed unit file
+fn disable(file: &Path) {
+    let name = file.file_name().unwrap_or_default();
+    let dirs = match file.parent().map(fs::read_dir) {
+        Some(Ok(entries)) => entries,
+        _ => return,
+    };
+    for wants in dirs
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|dir| dir.extension().is_some_and(|e| e == "wants"))
+    {
+        let links = match fs::read_dir(&wants) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for link in
+            links.filter_map(|entry| entry.ok().map(|e| e.path()))
+        {
+            let points_to_file =
+                fs::read_link(&link).is_ok_and(|target| {
+                    target.file_name() == Some(name)
+                        && (target.is_relative() || target == file)
+                });
+            if points_to_file && fs::remove_file(&link).is_ok() {
+                println!("Disabled {}", link.display());
+            }
+        }
+    }
+}
+
+/// Creates the directory for unit files when they are about to be written,
+/// a missing default directory hints at systemd not being installed
+fn create_output_dir(dir: &Path) {
+    if dir.is_dir() {
+        return;
+    }
+    fs::create_dir_all(dir).expect(
+        "Could not find or create the systemd config folder.",
+    );
+    if dir == default_output_dir() {
+        println!(
+            "Created {}, make sure you have systemd installed.",
+            dir.display()
+        );
+    }
+}
+
+/// $XDG_CONFIG_HOME/systemd/user, where user units go
+fn default_output_dir() -> PathBuf {
+    let mut dir = dirs::config_dir()
+        .expect("Could not find the config Directory");
+    dir.push("systemd/user");
+    dir
+}
+
+/// Checks existing unit files, exiting with an error if any has errors,
+/// warnings are only printed
+fn verify_files(files: &[PathBuf]) {
+    let mut failed = false;
+    for file in files {
+        let kind = file
+            .extension()
+            .and_then(|e| e.to_str())
+            .and_then(|e| UnitKind::from_str(e).ok())
+            .unwrap_or_else(|| {
+                panic!(
+                    "{} is not a service, timer, path or socket file",
+                    file.display()
+                )
+            });
+        let content = fs::read_to_string(file).unwrap_or_else(|e| {
+            panic!("Unable to read {}: {}", file.display(), e)
+        });
+        let report = match UnitFile::from_str(&content) {
+            Ok(unit) => verify::verify(&unit, kind),
+            Err(e) => verify::Report {
+                errors: vec![e.0],
+                warnings: Vec::new(),
+            },
+        };
+        for error in &report.errors {
+            println!("{}: {}", file.display(), error);
+        }
+        for warning in &report.warnings {
+            println!("{}: Warning: {}", file.display(), warning);
+        }
+        failed |= !report.errors.is_empty();
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// The `[Timer]` options set on the command line
+fn timer_options(opt: &Opt) -> Vec<(&'static str, String)> {
+    let mut options = Vec::new();
+    if opt.persistent {
+        options.push(("Persistent", String::from("true")));
+    }
+    if let Some(accuracy) = &opt.accuracy {
+        options.push(("AccuracySec", accuracy.to_string()));
+    }
+    if let Some(delay) = &opt.random_delay {
+        options.push(("RandomizedDelaySec", delay.to_string()));
+    }
+    if opt.fixed_random_delay {
+        if opt.random_delay.is_none() {
+            eprintln!(
+                "--fixed-random-delay has no effect without --random-delay"
+            )
+        }
+        options.push(("FixedRandomDelay", String::from("true")));
+    }
+    if opt.wake_system {
+        options.push(("WakeSystem", String::from("true")));
+    }
+    if let Some(remain) = opt.remain_after_elapse {
+        options.push(("RemainAfterElapse", remain.to_string()));
+    }
+    options
+}
+
+/// Resolves the path a `watch` target watches and warns about paths that
+/// can't trigger the way the user likely expects
+fn watched_path(path: &Path, trigger: PathTrigger) -> PathBuf {
+    let path = if path.is_absolute() {
+        path.to_owned()
     } else {
-        fs::write(&service_file, service)
-            .expect("Unable to write Service File");
-    }
-
-    if let Some(timer) = target.timer() {
-        let timer_content = formatdoc!(
-            "
-        [Unit]
-        Description=Runs {} on a timer
-        
-        [Timer]
-        {}
-
-        [Install]
-        WantedBy=timers.target
-        ",
-            service_name,
-            timer
+        let mut abs_path = unwrap!(
+            env::current_dir(),
+            "Unable to resolve relative path {} due to invalid working \
+            directory. Make sure autod has sufficient permissions or use \
+            an absolute path",
+            path.display()
         );
-        if opt.print {
-            printdoc!(
-                "
-                Timer File:
-                ===
-                {}
-                ===
-                ",
-                timer_content
-            );
-        } else {
-            fs::write(&timer_file, timer_content)
-                .expect("Unable to write Timer File");
+        abs_path.extend(
+            path.components().filter(|c| *c != Component::CurDir),
+        );
+        abs_path
+    };
+
+    match trigger {
+        PathTrigger::Changed | PathTrigger::Modified
+            if !path.exists() =>
+        {
+            eprintln!(
+                "{} does not exist yet, it will only trigger once it is \
+                created",
+                path.display()
+            )
+        }
+        PathTrigger::DirectoryNotEmpty
+            if path.exists() && !path.is_dir() =>
+        {
+            panic!(
+                "{} is not a directory, so it can never be not empty",
+                path.display()
+            )
+        }
+        _ => {}
+    }
+
+    // Globs are only expanded in the file name
+    if let Some(parent) = path.parent() {
+        if !parent.is_dir() {
+            eprintln!(
+                "{} does not exist, make sure it is created before the \
+                path unit is started",
+                parent.display()
+            )
+        }
+    }
+
+    path
+}
+
+/// Checks that the address fits the socket kind and warns about addresses
+/// a user service can not bind
+fn listen_address(
+    address: &ListenAddress,
+    kind: SocketKind,
+) -> String {
+    match (address, kind) {
+        (ListenAddress::Unix(_), _) => {}
+        (_, SocketKind::Fifo) => panic!(
+            "A FIFO needs an absolute path to listen on, not {}",
+            address
+        ),
+        _ => {}
+    }
+
+    match address {
+        ListenAddress::Unix(path) => {
+            if let Some(parent) = path.parent() {
+                if !parent.is_dir() {
+                    eprintln!(
+                        "{} does not exist, make sure it is created before \
+                        the socket unit is started",
+                        parent.display()
+                    )
+                }
+            }
         }
+        _ => {
+            if let Some(port) = address.port().filter(|p| *p < 1024) {
+                eprintln!(
+                    "Port {} is privileged, a user socket unit will fail \
+                    to bind it",
+                    port
+                )
+            }
+        }
+    }
+
+    address.to_string()
+}
+
+/// Enables instances of a template the way `systemctl enable` does, by
+/// linking them into the `.wants` directories of its WantedBy targets
+fn enable_instances(
+    template: &Path,
+    unit: &UnitFile,
+    instances: &[String],
+    print: bool,
+) {
+    let template_name =
+        template.file_name().unwrap().to_string_lossy();
+    let targets: Vec<&str> = unit
+        .get("Install", "WantedBy")
+        .flat_map(str::split_whitespace)
+        .collect();
+    if targets.is_empty() {
+        panic!(
+            "{} has no WantedBy= to enable instances for, use `on \
+            <target>` or --set Install.WantedBy=<target>",
+            template_name
+        )
+    }
+
+    let instance_names: Vec<String> = instances
+        .iter()
+        .map(|instance| {
+            template_name.replacen(
+                "@.",
+                &format!("@{}.", unit::escape_instance(instance)),
+                1,
+            )
+        })
+        .collect();
+
+    if print {
+        println!(
+            "Enable the instances with:\nsystemctl --user enable {}",
+            instance_names.join(" ")
+        );
+        return;
+    }
+
+    for target in targets {
+        let wants =
+            template.with_file_name(format!("{}.wants", target));
+        fs::create_dir_all(&wants).unwrap_or_else(|e| {
+            panic!("Could not create {}: {}", wants.display(), e)
+        });
+        for instance_name in &instance_names {
+            let link = wants.join(instance_name);
+            if link.symlink_metadata().is_ok() {
+                if !link.is_file() {
+                    panic!(
+                        "{} exists and is not a unit, consider moving it",
+                        link.display()
+                    )
+                }
+                fs::remove_file(&link).unwrap_or_else(|e| {
+                    panic!(
+                        "Could not replace {}: {}",
+                        link.display(),
+                        e
+                    )
+                });
+            }
+            std::os::unix::fs::symlink(
+                Path::new("..").join(template_name.as_ref()),
+                &link,
+            )
+            .unwrap_or_else(|e| {
+                panic!("Could not enable {}: {}", instance_name, e)
+            });
+            println!("Enabled {} for {}", instance_name, target);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The units `autod <args>` creates, without writing them
+    fn units(args: &[&str]) -> Units {
+        let args = ["autod"]
+            .iter()
+            .chain(args)
+            .chain(&["--output-dir=/nonexistent/autod"]);
+        generate(&Opt::try_parse_from(args).unwrap())
+    }
+
+    #[test]
+    fn dependencies_and_after() {
+        let units = units(&[
+            "/bin/true",
+            "after",
+            "network-online.target",
+            "--after=db.service",
+            "--requires=db.service",
+            "--wants=cache.socket",
+            "--binds-to=data.mount",
+            "--part-of=app.target",
+        ]);
+        let unit = |key| {
+            units.service.get("Unit", key).collect::<Vec<&str>>()
+        };
+        assert_eq!(
+            unit("After"),
+            ["network-online.target", "db.service"]
+        );
+        assert_eq!(
+            unit("Wants"),
+            ["network-online.target", "cache.socket"]
+        );
+        assert_eq!(unit("Requires"), ["db.service"]);
+        assert_eq!(unit("BindsTo"), ["data.mount"]);
+        assert_eq!(unit("PartOf"), ["app.target"]);
+        assert_eq!(
+            units
+                .service
+                .get("Install", "WantedBy")
+                .collect::<Vec<_>>(),
+            ["default.target"]
+        );
+        assert!(units.companion.is_none());
+        assert!(Opt::try_parse_from(["autod", "x", "--after=db"])
+            .is_err());
+    }
+
+    #[test]
+    fn template_instances() {
+        let paths = units(&[
+            "/usr/bin/backup",
+            "-n",
+            "backup",
+            "-t",
+            "-i",
+            "/home/me",
+            "-i",
+            "/srv//data/",
+        ]);
+        assert_eq!(
+            paths.service_file,
+            Path::new("/nonexistent/autod/backup@.service")
+        );
+        assert_eq!(
+            paths
+                .service
+                .get("Service", "ExecStart")
+                .collect::<Vec<_>>(),
+            ["/usr/bin/backup %f"]
+        );
+        assert_eq!(unit::escape_instance("/home/me"), "home-me");
+        assert_eq!(unit::escape_instance("/srv//data/"), "srv-data");
+        assert_eq!(unit::escape_instance("/"), "-");
+
+        let names = units(&[
+            "/usr/bin/backup",
+            "-n",
+            "backup",
+            "-t",
+            "-i",
+            "db",
+        ]);
+        assert_eq!(
+            names
+                .service
+                .get("Service", "ExecStart")
+                .collect::<Vec<_>>(),
+            ["/usr/bin/backup %I"]
+        );
+        assert_eq!(
+            names
+                .service
+                .get("Unit", "Description")
+                .collect::<Vec<_>>(),
+            ["Runs backup for %I, created by autod"]
+        );
+        assert_eq!(unit::escape_instance("my db"), "my\\x20db");
+
+        let explicit = units(&[
+            "/usr/bin/backup",
+            "-n",
+            "backup",
+            "-t",
+            "--arg=--host=%i",
+        ]);
+        assert_eq!(
+            explicit
+                .service
+                .get("Service", "ExecStart")
+                .collect::<Vec<_>>(),
+            ["/usr/bin/backup --host=%i"]
+        );
     }
 }
@@ -0,0 +1,349 @@
+use std::convert::TryFrom;
+
+use chrono::{Datelike, NaiveDate, Weekday};
+
+use super::calendar::ParseError;
+
+fn first_of_month(
+    year: i32,
+    month: u32,
+) -> Result<NaiveDate, ParseError> {
+    NaiveDate::from_ymd_opt(year, month, 1).ok_or_else(|| {
+        ParseError(format!(
+            "{}-{:02} is not a valid month",
+            year, month
+        ))
+    })
+}
+
+/// Number of days in `month` of `year`
+pub fn days_in_month(
+    year: i32,
+    month: u32,
+) -> Result<u32, ParseError> {
+    let first = first_of_month(year, month)?;
+    let next = if month == 12 {
+        first_of_month(year + 1, 1)?
+    } else {
+        first_of_month(year, month + 1)?
+    };
+    Ok((next - first).num_days() as u32)
+}
+
+/// Adds `months` to `date`, if the resulting month is shorter than the
+/// day of `date`, its last day is used instead
+pub fn add_months(
+    date: NaiveDate,
+    months: i64,
+) -> Result<NaiveDate, ParseError> {
+    let total = i64::from(date.year()) * 12
+        + i64::from(date.month0())
+        + months;
+    let year = i32::try_from(total.div_euclid(12)).map_err(|_| {
+        ParseError(format!(
+            "{} months from {} is too far",
+            months, date
+        ))
+    })?;
+    let month = total.rem_euclid(12) as u32 + 1;
+    let day = date.day().min(days_in_month(year, month)?);
+    Ok(first_of_month(year, month)?.with_day(day).unwrap())
+}
+
+/// The `day`th day of the month, negative days count from the end, so
+/// `-1` is the last day
+pub fn day_of_month(
+    year: i32,
+    month: u32,
+    day: i32,
+) -> Result<NaiveDate, ParseError> {
+    let days = days_in_month(year, month)? as i32;
+    let wanted = if day < 0 { days + 1 + day } else { day };
+    if wanted < 1 || wanted > days {
+        Err(ParseError(format!(
+            "{}-{:02} has no day {}, it has {} days",
+            year, month, day, days
+        )))?
+    }
+    Ok(first_of_month(year, month)?
+        .with_day(wanted as u32)
+        .unwrap())
+}
+
+/// The `n`th `weekday` of the month, negative `n` count from the end, so
+/// `-1` is the last one
+pub fn nth_weekday(
+    year: i32,
+    month: u32,
+    n: i8,
+    weekday: Weekday,
+) -> Result<NaiveDate, ParseError> {
+    let days = days_in_month(year, month)? as i32;
+    let wanted = weekday.num_days_from_monday() as i32;
+    let day = match n {
+        0 => Err(ParseError(String::from(
+            "There is no 0th weekday, use 1 for the first or -1 for the \
+            last",
+        )))?,
+        _ if n > 0 => {
+            let first = first_of_month(year, month)?
+                .weekday()
+                .num_days_from_monday() as i32;
+            1 + (7 + wanted - first) % 7 + 7 * (i32::from(n) - 1)
+        }
+        _ => {
+            let last = first_of_month(year, month)?
+                .with_day(days as u32)
+                .unwrap()
+                .weekday()
+                .num_days_from_monday() as i32;
+            days - (7 + last - wanted) % 7 - 7 * (-i32::from(n) - 1)
+        }
+    };
+    if day < 1 || day > days {
+        Err(ParseError(format!(
+            "{}-{:02} does not have {} {}s",
+            year,
+            month,
+            i32::from(n).abs(),
+            weekday
+        )))?
+    }
+    Ok(first_of_month(year, month)?.with_day(day as u32).unwrap())
+}
+
+/// The date of `month` and `day` in `year`, with a hint for Feb 29 in
+/// common years
+pub fn month_day(
+    year: i32,
+    month: u32,
+    day: u32,
+) -> Result<NaiveDate, ParseError> {
+    NaiveDate::from_ymd_opt(year, month, day).ok_or_else(|| {
+        ParseError(match (month, day) {
+            (2, 29) => format!(
+                "{} is not a leap year, so Feb 29 does not exist, use 02-28 \
+                or 03-01",
+                year
+            ),
+            _ => format!("{:02}-{:02} is not a valid date", month, day),
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    /// Every day from 2019 to 2029, covering the leap years 2020, 2024
+    /// and 2028
+    fn start_dates() -> impl Iterator<Item = NaiveDate> {
+        let mut day = date(2019, 1, 1);
+        std::iter::from_fn(move || {
+            let current = day;
+            day = day.succ_opt()?;
+            Some(current).filter(|d| d.year() < 2030)
+        })
+    }
+
+    fn is_leap(year: i32) -> bool {
+        year % 4 == 0 && (year % 100 != 0 || year % 400 == 0)
+    }
+
+    #[test]
+    fn days_in_month_sum_to_the_year() {
+        for year in (1896..2105).chain([1900, 2000, 2100]) {
+            let total: u32 = (1..=12)
+                .map(|month| days_in_month(year, month).unwrap())
+                .sum();
+            assert_eq!(total, if is_leap(year) { 366 } else { 365 });
+        }
+        assert!(days_in_month(2024, 0).is_err());
+        assert!(days_in_month(2024, 13).is_err());
+    }
+
+    #[test]
+    fn add_months_keeps_the_day_or_clamps() {
+        for start in start_dates() {
+            for months in -30..=30 {
+                let result = add_months(start, months).unwrap();
+                let total = i64::from(start.year()) * 12
+                    + i64::from(start.month0())
+                    + months;
+                assert_eq!(
+                    i64::from(result.year()),
+                    total.div_euclid(12)
+                );
+                assert_eq!(
+                    i64::from(result.month0()),
+                    total.rem_euclid(12)
+                );
+                let days =
+                    days_in_month(result.year(), result.month())
+                        .unwrap();
+                assert_eq!(result.day(), start.day().min(days));
+                if start.day() <= 28 {
+                    assert_eq!(
+                        add_months(result, -months).unwrap(),
+                        start
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn add_months_at_the_end_of_months() {
+        assert_eq!(
+            add_months(date(2024, 1, 31), 1).unwrap(),
+            date(2024, 2, 29)
+        );
+        assert_eq!(
+            add_months(date(2023, 1, 31), 1).unwrap(),
+            date(2023, 2, 28)
+        );
+        assert_eq!(
+            add_months(date(2023, 1, 30), 1).unwrap(),
+            date(2023, 2, 28)
+        );
+        assert_eq!(
+            add_months(date(2024, 1, 29), 1).unwrap(),
+            date(2024, 2, 29)
+        );
+        assert_eq!(
+            add_months(date(2024, 3, 31), 1).unwrap(),
+            date(2024, 4, 30)
+        );
+        assert_eq!(
+            add_months(date(2024, 3, 31), -1).unwrap(),
+            date(2024, 2, 29)
+        );
+        assert_eq!(
+            add_months(date(2024, 2, 29), 12).unwrap(),
+            date(2025, 2, 28)
+        );
+        assert_eq!(
+            add_months(date(2024, 2, 29), 48).unwrap(),
+            date(2028, 2, 29)
+        );
+        assert_eq!(
+            add_months(date(2023, 12, 31), 1).unwrap(),
+            date(2024, 1, 31)
+        );
+        assert_eq!(
+            add_months(date(2024, 1, 15), -1).unwrap(),
+            date(2023, 12, 15)
+        );
+        assert!(add_months(date(2024, 1, 1), i64::MAX / 2).is_err());
+    }
+
+    #[test]
+    fn day_of_month_counts_from_both_ends() {
+        for start in start_dates().filter(|d| d.day() == 1) {
+            let (year, month) = (start.year(), start.month());
+            let days = days_in_month(year, month).unwrap() as i32;
+            for day in 1..=days {
+                let from_start =
+                    day_of_month(year, month, day).unwrap();
+                let from_end =
+                    day_of_month(year, month, day - days - 1)
+                        .unwrap();
+                assert_eq!(from_start, from_end);
+                assert_eq!(from_start.day() as i32, day);
+            }
+            assert!(day_of_month(year, month, 0).is_err());
+            assert!(day_of_month(year, month, days + 1).is_err());
+            assert!(day_of_month(year, month, -days - 1).is_err());
+        }
+        assert_eq!(
+            day_of_month(2024, 2, -1).unwrap(),
+            date(2024, 2, 29)
+        );
+        assert_eq!(
+            day_of_month(2023, 2, -1).unwrap(),
+            date(2023, 2, 28)
+        );
+        assert!(day_of_month(2023, 2, 29).is_err());
+        assert!(day_of_month(2024, 4, 31).is_err());
+    }
+
+    #[test]
+    fn nth_weekday_finds_every_occurrence() {
+        let weekdays = [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ];
+        for start in start_dates().filter(|d| d.day() == 1) {
+            let (year, month) = (start.year(), start.month());
+            let days = days_in_month(year, month).unwrap();
+            for weekday in weekdays.iter().copied() {
+                let all: Vec<NaiveDate> = (1..=days)
+                    .map(|day| date(year, month, day))
+                    .filter(|d| d.weekday() == weekday)
+                    .collect();
+                for (i, expected) in all.iter().enumerate() {
+                    let n = i as i8 + 1;
+                    let last = -((all.len() - i) as i8);
+                    assert_eq!(
+                        nth_weekday(year, month, n, weekday).unwrap(),
+                        *expected
+                    );
+                    assert_eq!(
+                        nth_weekday(year, month, last, weekday)
+                            .unwrap(),
+                        *expected
+                    );
+                }
+                let past = all.len() as i8 + 1;
+                assert!(
+                    nth_weekday(year, month, past, weekday).is_err()
+                );
+                assert!(
+                    nth_weekday(year, month, -past, weekday).is_err()
+                );
+                assert!(nth_weekday(year, month, 0, weekday).is_err());
+            }
+        }
+        // The last Friday, and a fifth Monday only some months have
+        assert_eq!(
+            nth_weekday(2026, 10, -1, Weekday::Fri).unwrap(),
+            date(2026, 10, 30)
+        );
+        assert_eq!(
+            nth_weekday(2024, 2, 5, Weekday::Thu).unwrap(),
+            date(2024, 2, 29)
+        );
+        assert!(nth_weekday(2023, 2, 5, Weekday::Mon).is_err());
+    }
+
+    #[test]
+    fn month_day_explains_leap_days() {
+        for year in 2019..2030 {
+            match month_day(year, 2, 29) {
+                Ok(day) => {
+                    assert!(is_leap(year));
+                    assert_eq!(day, date(year, 2, 29));
+                }
+                Err(e) => {
+                    assert!(!is_leap(year));
+                    assert!(
+                        e.0.contains("not a leap year"),
+                        "{}",
+                        e.0
+                    );
+                }
+            }
+        }
+        assert!(month_day(2024, 4, 31).is_err());
+        assert!(month_day(2024, 13, 1).is_err());
+    }
+}
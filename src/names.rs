@@ -0,0 +1,354 @@
+use std::str::FromStr;
+
+use chrono::Weekday;
+
+use super::calendar::ParseError;
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+
+#[cfg(feature = "german")]
+const WEEKDAYS_DE: [(&str, Weekday); 7] = [
+    ("montag", Weekday::Mon),
+    ("dienstag", Weekday::Tue),
+    ("mittwoch", Weekday::Wed),
+    ("donnerstag", Weekday::Thu),
+    ("freitag", Weekday::Fri),
+    ("samstag", Weekday::Sat),
+    ("sonntag", Weekday::Sun),
+];
+
+const MONTHS: [(&str, u32); 12] = [
+    ("january", 1),
+    ("february", 2),
+    ("march", 3),
+    ("april", 4),
+    ("may", 5),
+    ("june", 6),
+    ("july", 7),
+    ("august", 8),
+    ("september", 9),
+    ("october", 10),
+    ("november", 11),
+    ("december", 12),
+];
+
+#[cfg(feature = "german")]
+const MONTHS_DE: [(&str, u32); 13] = [
+    ("januar", 1),
+    ("februar", 2),
+    ("märz", 3),
+    ("maerz", 3),
+    ("april", 4),
+    ("mai", 5),
+    ("juni", 6),
+    ("juli", 7),
+    ("august", 8),
+    ("september", 9),
+    ("oktober", 10),
+    ("november", 11),
+    ("dezember", 12),
+];
+
+fn weekday_names(
+) -> impl Iterator<Item = &'static (&'static str, Weekday)> {
+    let names = WEEKDAYS.iter();
+    #[cfg(feature = "german")]
+    let names = names.chain(WEEKDAYS_DE.iter());
+    names
+}
+
+fn month_names() -> impl Iterator<Item = &'static (&'static str, u32)>
+{
+    let names = MONTHS.iter();
+    #[cfg(feature = "german")]
+    let names = names.chain(MONTHS_DE.iter());
+    names
+}
+
+/// The value of the names starting with `name`, if they all agree, so
+/// abbreviations need at least `min` letters and to be unambiguous
+fn lookup<T: Copy + PartialEq + 'static>(
+    names: impl Iterator<Item = &'static (&'static str, T)>,
+    name: &str,
+    min: usize,
+) -> Option<T> {
+    let name = name.trim().trim_end_matches('.').to_lowercase();
+    if name.chars().count() < min {
+        return None;
+    }
+    let mut found = names
+        .filter(|(full, _)| full.starts_with(name.as_str()))
+        .map(|(_, value)| *value);
+    let first = found.next()?;
+    if found.all(|value| value == first) {
+        Some(first)
+    } else {
+        None
+    }
+}
+
+/// The weekday from its name or an abbreviation like `Tu` or `Thurs`
+pub fn weekday(name: &str) -> Option<Weekday> {
+    lookup(weekday_names(), name, 2)
+}
+
+/// The number of a month from its name or an abbreviation like `Sep`
+pub fn month(name: &str) -> Option<u32> {
+    lookup(month_names(), name, 3)
+}
+
+/// The English name of a weekday
+pub fn weekday_name(day: Weekday) -> &'static str {
+    match day {
+        Weekday::Mon => "Monday",
+        Weekday::Tue => "Tuesday",
+        Weekday::Wed => "Wednesday",
+        Weekday::Thu => "Thursday",
+        Weekday::Fri => "Friday",
+        Weekday::Sat => "Saturday",
+        Weekday::Sun => "Sunday",
+    }
+}
+
+/// The English name of a month, counted from 1
+pub fn month_name(month: u32) -> &'static str {
+    [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ][(month as usize - 1) % 12]
+}
+
+/// Splits a list item into the ends of a `from..to` or `from-to` range
+fn range(item: &str) -> (&str, Option<&str>) {
+    let item = item.trim();
+    match item.split_once("..") {
+        Some((from, to)) => (from.trim(), Some(to.trim())),
+        None => match item.split_once('-') {
+            Some((from, to)) if !from.is_empty() => {
+                (from.trim(), Some(to.trim()))
+            }
+            _ => (item, None),
+        },
+    }
+}
+
+/// Parses weekdays like `Mon,Wed,Fri`, `Mon..Fri` or `Mon-Fri` into the
+/// set of days, indexed from Monday, ranges like `Sat..Mon` wrap around
+pub fn weekday_set(list: &str) -> Result<[bool; 7], ParseError> {
+    let mut days = [false; 7];
+    for item in list.trim_end_matches(',').split(',') {
+        let day = |name: &str| {
+            weekday(name).ok_or_else(|| {
+                ParseError(format!("`{}` is not a weekday", name))
+            })
+        };
+        let (from, to) = range(item);
+        let mut from = day(from)?;
+        let to = to.map(day).transpose()?.unwrap_or(from);
+        days[to.num_days_from_monday() as usize] = true;
+        while from != to {
+            days[from.num_days_from_monday() as usize] = true;
+            from = from.succ();
+        }
+    }
+    Ok(days)
+}
+
+/// The days of a set from `weekday_set`, written like systemd does with
+/// runs of three or more days as range
+pub fn weekday_ranges(
+    days: &[bool; 7],
+) -> Vec<(Weekday, Option<Weekday>)> {
+    let weekday =
+        |n: usize| (0..n).fold(Weekday::Mon, |d, _| d.succ());
+    let mut ranges = Vec::new();
+    let mut day = 0;
+    while day < 7 {
+        let run = days[day..].iter().take_while(|d| **d).count();
+        match run {
+            0 => day += 1,
+            1 | 2 => {
+                ranges.extend(
+                    (day..day + run).map(|d| (weekday(d), None)),
+                );
+                day += run;
+            }
+            _ => {
+                ranges.push((
+                    weekday(day),
+                    Some(weekday(day + run - 1)),
+                ));
+                day += run;
+            }
+        }
+    }
+    ranges
+}
+
+/// Parses months like `Jan,Apr`, `Jan..Mar` or `Jan-Mar` into sorted
+/// `from..to` ranges, numbers work too and ranges like `Nov-Feb` wrap
+/// around the end of the year
+pub fn month_ranges(
+    list: &str,
+) -> Result<Vec<(u32, u32)>, ParseError> {
+    let month = |name: &str| {
+        month(name)
+            .or_else(|| {
+                u32::from_str(name)
+                    .ok()
+                    .filter(|m| (1..=12).contains(m))
+            })
+            .ok_or_else(|| {
+                ParseError(format!("`{}` is not a month", name))
+            })
+    };
+    let mut ranges = Vec::new();
+    for item in list.trim_end_matches(',').split(',') {
+        let (from, to) = range(item);
+        let from = month(from)?;
+        let to = to.map(month).transpose()?.unwrap_or(from);
+        if to < from {
+            ranges.push((from, 12));
+            ranges.push((1, to));
+        } else {
+            ranges.push((from, to));
+        }
+    }
+    ranges.sort_unstable();
+    ranges.dedup();
+    Ok(ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn abbreviations() {
+        assert_eq!(weekday("Tu"), Some(Weekday::Tue));
+        assert_eq!(weekday("thurs."), Some(Weekday::Thu));
+        assert_eq!(weekday("SUNDAY"), Some(Weekday::Sun));
+        assert_eq!(weekday("T"), None);
+        assert_eq!(weekday("Mondays"), None);
+        assert_eq!(month("Sep"), Some(9));
+        assert_eq!(month("sept."), Some(9));
+        assert_eq!(month("Ma"), None);
+        assert_eq!(month("Jun"), Some(6));
+    }
+
+    #[test]
+    fn weekday_sets() {
+        let set = |list| weekday_set(list).unwrap();
+        assert_eq!(
+            set("Mon..Fri"),
+            [true, true, true, true, true, false, false]
+        );
+        assert_eq!(set("Mon-Fri"), set("Mon..Fri"));
+        assert_eq!(
+            set("Fri..Mon"),
+            [true, false, false, false, true, true, true]
+        );
+        assert_eq!(
+            set("Sat,Sun,"),
+            [false, false, false, false, false, true, true]
+        );
+        assert_eq!(set("Wed..Wed"), set("Wed"));
+        assert!(weekday_set("Mon..Xyz")
+            .unwrap_err()
+            .0
+            .contains("Xyz"));
+        assert!(weekday_set("").is_err());
+    }
+
+    #[test]
+    fn weekday_runs() {
+        let ranges =
+            |list| weekday_ranges(&weekday_set(list).unwrap());
+        assert_eq!(
+            ranges("Mon..Fri"),
+            [(Weekday::Mon, Some(Weekday::Fri))]
+        );
+        assert_eq!(
+            ranges("Fri..Mon"),
+            [
+                (Weekday::Mon, None),
+                (Weekday::Fri, Some(Weekday::Sun))
+            ]
+        );
+        assert_eq!(
+            ranges("Sat,Sun,Tue"),
+            [
+                (Weekday::Tue, None),
+                (Weekday::Sat, None),
+                (Weekday::Sun, None)
+            ]
+        );
+        assert_eq!(
+            ranges("Mon,Tue,Wed,Fri"),
+            [
+                (Weekday::Mon, Some(Weekday::Wed)),
+                (Weekday::Fri, None)
+            ]
+        );
+        assert!(weekday_ranges(&[false; 7]).is_empty());
+    }
+
+    #[test]
+    fn month_lists() {
+        assert_eq!(month_ranges("Jan..Mar").unwrap(), [(1, 3)]);
+        assert_eq!(month_ranges("jun-aug").unwrap(), [(6, 8)]);
+        assert_eq!(
+            month_ranges("Nov-Feb").unwrap(),
+            [(1, 2), (11, 12)]
+        );
+        assert_eq!(
+            month_ranges("Dec,4,Jan,Apr").unwrap(),
+            [(1, 1), (4, 4), (12, 12)]
+        );
+        assert_eq!(month_ranges("3..5,").unwrap(), [(3, 5)]);
+        for list in ["Foo", "13", "0", "Jan..", "Jan,,Feb"] {
+            assert!(month_ranges(list).is_err(), "{}", list);
+        }
+    }
+
+    #[cfg(feature = "german")]
+    #[test]
+    fn german_names() {
+        assert_eq!(weekday("Mittwoch"), Some(Weekday::Wed));
+        assert_eq!(weekday("Do"), Some(Weekday::Thu));
+        assert_eq!(weekday("Son"), Some(Weekday::Sun));
+        // Mo and Sa start names in both languages, which agree
+        assert_eq!(weekday("Mo"), Some(Weekday::Mon));
+        assert_eq!(weekday("Sa"), Some(Weekday::Sat));
+        assert_eq!(month("Mär"), Some(3));
+        assert_eq!(month("maerz"), Some(3));
+        assert_eq!(month("Okt"), Some(10));
+        assert_eq!(month("Dez."), Some(12));
+        assert_eq!(
+            weekday_set("Fr..Mo").unwrap(),
+            [true, false, false, false, true, true, true]
+        );
+        assert_eq!(
+            month_ranges("Okt-Mär").unwrap(),
+            [(1, 3), (10, 12)]
+        );
+    }
+}
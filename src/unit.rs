@@ -0,0 +1,216 @@
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+use crate::cli::calendar::ParseError;
+
+/// The sections and `Key=Value` lines of a systemd unit file, in the
+/// order they are written
+#[derive(Debug, Default)]
+pub struct UnitFile {
+    comments: Vec<String>,
+    sections: Vec<(String, Vec<(String, String)>)>,
+}
+
+impl UnitFile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn section_mut(
+        &mut self,
+        section: &str,
+    ) -> &mut Vec<(String, String)> {
+        let index = match self
+            .sections
+            .iter()
+            .position(|(name, _)| name == section)
+        {
+            Some(index) => index,
+            None => {
+                self.sections.push((section.to_owned(), Vec::new()));
+                self.sections.len() - 1
+            }
+        };
+        &mut self.sections[index].1
+    }
+
+    /// Adds a `# comment` line, written before the first section
+    pub fn comment(
+        &mut self,
+        comment: impl Into<String>,
+    ) -> &mut Self {
+        self.comments.push(comment.into());
+        self
+    }
+
+    /// Appends `key=value` to `section`, creating the section if needed
+    pub fn add(
+        &mut self,
+        section: &str,
+        key: &str,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        self.section_mut(section)
+            .push((key.to_owned(), value.into()));
+        self
+    }
+
+    /// Replaces every `key` in `section` with a single `key=value`
+    pub fn set(
+        &mut self,
+        section: &str,
+        key: &str,
+        value: impl Into<String>,
+    ) -> &mut Self {
+        self.section_mut(section).retain(|(k, _)| k != key);
+        self.add(section, key, value)
+    }
+
+    /// Appends newline separated `Key=Value` lines, as returned by
+    /// `TimerAble::timer()`
+    pub fn add_lines(
+        &mut self,
+        section: &str,
+        lines: &str,
+    ) -> &mut Self {
+        for line in lines.lines().filter(|l| !l.trim().is_empty()) {
+            let (key, value) =
+                line.split_once('=').unwrap_or_else(|| {
+                    panic!("`{}` is not a valid directive", line)
+                });
+            self.add(section, key.trim(), value.trim());
+        }
+        self
+    }
+
+    /// The sections with their `(key, value)` entries, in file order
+    pub fn sections(
+        &self,
+    ) -> impl Iterator<Item = (&str, &[(String, String)])> {
+        self.sections.iter().map(|(name, entries)| {
+            (name.as_str(), entries.as_slice())
+        })
+    }
+
+    pub fn get<'a>(
+        &'a self,
+        section: &'a str,
+        key: &'a str,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        self.sections
+            .iter()
+            .filter(move |(name, _)| name == section)
+            .flat_map(|(_, entries)| entries.iter())
+            .filter(move |(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+impl Display for UnitFile {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for comment in &self.comments {
+            writeln!(f, "# {}", comment)?;
+        }
+        for (i, (name, entries)) in self.sections.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(f, "[{}]", name)?;
+            for (key, value) in entries {
+                writeln!(f, "{}={}", key, value)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads an existing unit file, comments are dropped and lines ending in
+/// `\` are joined with the next one
+impl FromStr for UnitFile {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut unit = UnitFile::new();
+        let mut section = None;
+        let mut lines = s.lines().enumerate();
+        while let Some((number, line)) = lines.next() {
+            let mut line = line.trim().to_owned();
+            while line.ends_with('\\') {
+                line.pop();
+                match lines.next() {
+                    Some((_, next)) => {
+                        line.push(' ');
+                        line.push_str(next.trim());
+                    }
+                    None => break,
+                }
+            }
+            if line.is_empty() || line.starts_with(['#', ';']) {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                section = Some(line[1..line.len() - 1].to_owned());
+                continue;
+            }
+            let (key, value) =
+                line.split_once('=').ok_or_else(|| {
+                    ParseError(format!(
+                        "line {}: `{}` is not a Key=Value line",
+                        number + 1,
+                        line
+                    ))
+                })?;
+            let section = section.as_ref().ok_or_else(|| {
+                ParseError(format!(
+                    "line {}: `{}` is outside of a section",
+                    number + 1,
+                    line
+                ))
+            })?;
+            unit.add(section, key.trim(), value.trim());
+        }
+        Ok(unit)
+    }
+}
+
+/// Quotes a command line argument for `ExecStart=` if needed
+pub fn quote(arg: &str) -> String {
+    if !arg.is_empty()
+        && !arg
+            .chars()
+            .any(|c| c.is_whitespace() || "\"'\\;".contains(c))
+    {
+        return arg.to_owned();
+    }
+    format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Escapes an instance name like `systemd-escape` does, absolute paths
+/// are escaped like with `--path`
+pub fn escape_instance(instance: &str) -> String {
+    let instance = if instance.starts_with('/') {
+        let path = instance
+            .split('/')
+            .filter(|c| !c.is_empty())
+            .collect::<Vec<&str>>()
+            .join("/");
+        if path.is_empty() {
+            return String::from("-");
+        }
+        path
+    } else {
+        instance.to_owned()
+    };
+    instance
+        .bytes()
+        .enumerate()
+        .map(|(i, b)| match b {
+            b'/' => String::from("-"),
+            b'.' if i == 0 => String::from("\\x2e"),
+            b if b.is_ascii_alphanumeric() || b":_.".contains(&b) => {
+                (b as char).to_string()
+            }
+            b => format!("\\x{:02x}", b),
+        })
+        .collect()
+}
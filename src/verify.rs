@@ -0,0 +1,306 @@
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::process::{Command, Output};
+use std::str::FromStr;
+
+use crate::cli::calendar::{InDuration, ParseError, Timer};
+use crate::cli::directive::{Section, UnitKind, UnitName};
+use crate::unit::UnitFile;
+
+/// Directories systemd searches for an `ExecStart=` command given without
+/// a path
+const EXEC_PATH: [&str; 6] = [
+    "/usr/local/sbin",
+    "/usr/local/bin",
+    "/usr/sbin",
+    "/usr/bin",
+    "/sbin",
+    "/bin",
+];
+
+/// Directives holding a list of unit names
+const UNIT_LISTS: [&str; 10] = [
+    "After",
+    "Before",
+    "Requires",
+    "Wants",
+    "BindsTo",
+    "PartOf",
+    "Conflicts",
+    "WantedBy",
+    "RequiredBy",
+    "Also",
+];
+
+/// What `verify` found in a unit file, one message per problem
+#[derive(Debug, Default)]
+pub struct Report {
+    /// Mistakes that keep the unit from working
+    pub errors: Vec<String>,
+    /// Lines systemd ignores or that likely don't do what was meant
+    pub warnings: Vec<String>,
+}
+
+/// Checks a unit file for the important mistakes `systemd-analyze verify`
+/// reports
+///
+/// Unknown sections and keys are only warnings, like systemd ignores them,
+/// as autod doesn't know every directive.
+pub fn verify(unit: &UnitFile, kind: UnitKind) -> Report {
+    let mut report = Report::default();
+    for (name, entries) in unit.sections() {
+        let section = match Section::from_str(name) {
+            Ok(section)
+                if section.kind().is_none_or(|k| k == kind) =>
+            {
+                section
+            }
+            Ok(section) => {
+                report.warnings.push(format!(
+                    "[{}] is a section of .{} files, systemd ignores it \
+                    in a .{} file",
+                    name,
+                    section.kind().unwrap(),
+                    kind
+                ));
+                continue;
+            }
+            Err(e) => {
+                report.warnings.push(e.0);
+                continue;
+            }
+        };
+        for (key, value) in entries {
+            if let Err(e) = section.check_key(key) {
+                report.warnings.push(e.0);
+            } else if let Err(e) = check_value(key, value) {
+                report
+                    .errors
+                    .push(format!("[{}] {}: {}", name, key, e));
+            }
+        }
+    }
+
+    if kind == UnitKind::Service {
+        match unit.get("Service", "ExecStart").next() {
+            Some(exec) => {
+                if let Err(e) = check_exec(exec) {
+                    report
+                        .errors
+                        .push(format!("[Service] ExecStart: {}", e));
+                }
+            }
+            None => report.errors.push(String::from(
+                "The service has no ExecStart=, so there is nothing to run",
+            )),
+        }
+    }
+
+    for target in unit
+        .get("Install", "WantedBy")
+        .flat_map(str::split_whitespace)
+        .filter(|target| !target.ends_with(".target"))
+    {
+        report.warnings.push(format!(
+            "[Install] WantedBy: {} is not a target, use After= and \
+            Wants= to start after other units",
+            target
+        ));
+    }
+
+    if kind == UnitKind::Timer
+        && !unit.sections().any(|(name, _)| name == "Install")
+    {
+        report.warnings.push(String::from(
+            "The timer has no [Install] section, so it can't be enabled",
+        ));
+    }
+    report
+}
+
+/// Parses the values autod understands, time spans, calendar events and
+/// unit names
+fn check_value(key: &str, value: &str) -> Result<(), ParseError> {
+    match key {
+        "OnCalendar" => {
+            Timer::from_str(&format!("OnCalendar={}", value))?;
+        }
+        _ if key.ends_with("Sec") => {
+            InDuration::from_str(value)?;
+        }
+        _ if UNIT_LISTS.contains(&key) => {
+            for unit in value.split_whitespace() {
+                UnitName::from_str(unit)?;
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Checks that the command of an `ExecStart=` line exists and can be
+/// executed
+fn check_exec(exec: &str) -> Result<(), ParseError> {
+    let exec = exec.trim_start_matches(|c| "@-:+!".contains(c));
+    let command = match exec.strip_prefix('"') {
+        Some(quoted) => quoted.split('"').next().unwrap(),
+        None => exec.split_whitespace().next().unwrap_or_default(),
+    };
+    // Specifiers like `%h` are only known once systemd loads the unit
+    if command.contains('%') {
+        return Ok(());
+    }
+    if command.is_empty() {
+        Err(ParseError(String::from("there is no command")))?;
+    }
+    let path = Path::new(command);
+    let path = if path.is_absolute() {
+        path.to_owned()
+    } else if command.contains('/') {
+        Err(ParseError(format!(
+            "`{}` is relative, systemd needs an absolute path",
+            command
+        )))?
+    } else {
+        EXEC_PATH
+            .iter()
+            .map(|dir| Path::new(dir).join(command))
+            .find(|path| path.exists())
+            .ok_or_else(|| {
+                ParseError(format!(
+                    "`{}` is not in systemd's search path, use an \
+                    absolute path",
+                    command
+                ))
+            })?
+    };
+    match path.metadata() {
+        Err(_) => Err(ParseError(format!(
+            "`{}` does not exist",
+            path.display()
+        ))),
+        Ok(meta)
+            if meta.is_dir()
+                || meta.permissions().mode() & 0o111 == 0 =>
+        {
+            Err(ParseError(format!(
+                "`{}` is not executable",
+                path.display()
+            )))
+        }
+        Ok(_) => Ok(()),
+    }
+}
+
+fn run(
+    systemd_analyze: &Path,
+    args: &[&str],
+) -> Result<Output, String> {
+    Command::new(systemd_analyze).args(args).output().map_err(|e| {
+        format!(
+            "Unable to run {}: {}, use --systemd-analyze to give its path",
+            systemd_analyze.display(),
+            e
+        )
+    })
+}
+
+/// Runs `systemd-analyze calendar` on an `OnCalendar=` value, failing if
+/// systemd rejects it, a next elapse different from autod's is returned
+/// as warning
+pub fn analyze_calendar(
+    systemd_analyze: &Path,
+    value: &str,
+) -> Result<Option<String>, String> {
+    let output = run(systemd_analyze, &["calendar", value])?;
+    if !output.status.success() {
+        Err(format!(
+            "systemd-analyze rejects OnCalendar={}: {}",
+            value,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))?;
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    // `Next elapse: Mon 2026-10-26 09:00:00 UTC` or `never`
+    let systemd = stdout
+        .lines()
+        .find_map(|line| line.trim().strip_prefix("Next elapse:"))
+        .map(|next| {
+            next.split_whitespace()
+                .take(3)
+                .collect::<Vec<&str>>()
+                .join(" ")
+        })
+        .unwrap_or_default();
+    let autod = Timer::from_str(&format!("OnCalendar={}", value))
+        .map_err(|e| e.0)?
+        .next_elapse()
+        .map_or_else(
+            || String::from("never"),
+            |next| next.format("%a %Y-%m-%d %H:%M:%S").to_string(),
+        );
+    Ok((systemd != autod).then(|| {
+        format!(
+            "OnCalendar={} elapses next on {} according to \
+            systemd-analyze, but on {} according to autod",
+            value, systemd, autod
+        )
+    }))
+}
+
+/// Runs `systemd-analyze verify` on written unit files, returning what it
+/// complains about
+pub fn analyze_files(
+    systemd_analyze: &Path,
+    files: &[&Path],
+) -> Result<Option<String>, String> {
+    // Without `--user`, as that needs a running user manager
+    let mut args = vec!["verify"];
+    args.extend(files.iter().filter_map(|file| file.to_str()));
+    let output = run(systemd_analyze, &args)?;
+    let complaints = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let complaints = complaints.trim();
+    Ok((!complaints.is_empty()).then(|| complaints.to_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(content: &str, kind: UnitKind) -> Report {
+        verify(&UnitFile::from_str(content).unwrap(), kind)
+    }
+
+    #[test]
+    fn unknown_keys_are_warnings() {
+        let report = report(
+            "[Unit]\nDescription=Backup\n\n\
+            [Service]\nExecStart=/bin/true\nProtectClock=yes\n\
+            X-Backup=daily\nFrobnicate=1\n\n\
+            [Timer]\nOnCalendar=daily\n",
+            UnitKind::Service,
+        );
+        assert!(report.errors.is_empty(), "{:?}", report.errors);
+        assert_eq!(report.warnings.len(), 2, "{:?}", report.warnings);
+        assert!(report.warnings[0].contains("Frobnicate"));
+        assert!(
+            report.warnings[1].starts_with("[Timer] is a section of")
+        );
+    }
+
+    #[test]
+    fn invalid_values_are_errors() {
+        let report = report(
+            "[Timer]\nOnCalendar=notadate\nAccuracySec=infinity\n\n\
+            [Install]\nWantedBy=timers.target\n",
+            UnitKind::Timer,
+        );
+        assert_eq!(report.errors.len(), 1, "{:?}", report.errors);
+        assert!(report.errors[0].starts_with("[Timer] OnCalendar:"));
+        assert!(report.warnings.is_empty(), "{:?}", report.warnings);
+    }
+}